@@ -8,8 +8,6 @@ pub struct Animation {
     height: f32,
     frames_hor: f32,
     frames_ver: f32,
-    curr_frame: f32,
-    src_rect: graphics::Rect,
 }
 
 impl Animation {
@@ -23,25 +21,40 @@ impl Animation {
                 height: 32.0,        // height slike robin_run* u pikselima
                 frames_hor: 7.0,
                 frames_ver: 1.0,
-                curr_frame: 0.0,
-                src_rect: graphics::Rect::new(0.0,0.0,1.0/7.0, 1.0),
             }
         }
 
+    pub fn draw(&self,ctx: &mut Context, pos: mint::Point2<f32>, frames: &Frames) -> GameResult<()> {
+        // koji deo slike crtamo zavisi samo od trenutnog frejma iz simulacije
+        let src_rect = graphics::Rect::new(frames.curr_frame/self.frames_hor, 0.0, 1.0/self.frames_hor, 1.0/self.frames_ver);
+        // y ne moramo da pomeramo jer je slika horizontalna
+        graphics::draw(ctx, &self.spritesheet, graphics::DrawParam::new().src(src_rect).dest(pos))?;
+        Ok(())
+    }
+}
+
+// Brojac frejmova animacije, ne zavisi od ggez konteksta pa
+// moze da zivi u simulaciji, a Animation ga samo procita pri crtanju
+#[derive(Debug, Clone)]
+pub struct Frames {
+    curr_frame: f32,
+    frame_count: f32,
+}
+
+impl Frames {
+    pub fn new(frame_count: f32) -> Self {
+        Frames {
+            curr_frame: 0.0,
+            frame_count: frame_count,
+        }
+    }
+
     pub fn reset(&mut self) {
         self.curr_frame = 0.0;
-        self.src_rect.x = 0.0;
     }
 
     pub fn next_frame(&mut self) {
-        self.curr_frame = ((self.curr_frame as i32 + 1) % 7) as f32;
-        self.src_rect.x = self.curr_frame/self.frames_hor;
-        // y ne moramo da pomeramo jer je slika horizontalna
-    }
-
-    pub fn draw(&self,ctx: &mut Context, pos: mint::Point2<f32>) -> GameResult<()> {
-        graphics::draw(ctx, &self.spritesheet, graphics::DrawParam::new().src(self.src_rect).dest(pos))?;
-        Ok(())
+        self.curr_frame = ((self.curr_frame as i32 + 1) % self.frame_count as i32) as f32;
     }
 }
 
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use ggez::{filesystem, input, Context, GameResult};
+use ggez::event::KeyCode;
+
+pub const BINDINGS_FILENAME: &str = "bindings.txt";
+
+// Sta igrac moze da uradi, nezavisno od toga kojim tasterom
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Interact,
+    Sneak,
+    Sprint,
+    Throw,
+    Pause,
+    TogglePaths,
+    ZoomIn,
+    ZoomOut,
+    ToggleFullscreen,
+}
+
+impl Action {
+    pub const ALL: [Action; 13] = [Action::MoveUp, Action::MoveDown, Action::MoveLeft, Action::MoveRight,
+                                   Action::Interact, Action::Sneak, Action::Sprint, Action::Throw, Action::Pause, Action::TogglePaths,
+                                   Action::ZoomIn, Action::ZoomOut, Action::ToggleFullscreen];
+
+    pub fn name(&self) -> &'static str {
+        // ime u fajlu sa tasterima
+        match self {
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Interact => "interact",
+            Action::Sneak => "sneak",
+            Action::Sprint => "sprint",
+            Action::Throw => "throw",
+            Action::Pause => "pause",
+            Action::TogglePaths => "toggle_paths",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::ToggleFullscreen => "toggle_fullscreen",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        // ime na ekranu za promenu tastera
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Interact => "Exit",
+            Action::Sneak => "Sneak",
+            Action::Sprint => "Sprint",
+            Action::Throw => "Throw pebble",
+            Action::Pause => "Pause",
+            Action::TogglePaths => "Guard paths",
+            Action::ZoomIn => "Zoom in",
+            Action::ZoomOut => "Zoom out",
+            Action::ToggleFullscreen => "Fullscreen",
+        }
+    }
+}
+
+// Tasteri koji mogu da se dodele akcijama. U fajlu se pisu imenom iz KeyCode (npr. W, Up, Escape)
+const KEYS: [KeyCode; 66] = [
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G, KeyCode::H, KeyCode::I,
+    KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N, KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R,
+    KeyCode::S, KeyCode::T, KeyCode::U, KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right,
+    KeyCode::Space, KeyCode::Return, KeyCode::Escape, KeyCode::Tab, KeyCode::Back,
+    KeyCode::LShift, KeyCode::RShift, KeyCode::LControl, KeyCode::RControl, KeyCode::LAlt, KeyCode::RAlt,
+    KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+    KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+    KeyCode::Delete, KeyCode::Equals, KeyCode::Minus,
+];
+
+fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    KEYS.iter().cloned().find(|&k| key_name(k) == name)
+}
+
+pub fn is_bindable(key: KeyCode) -> bool {
+    KEYS.contains(&key)
+}
+
+pub fn display_name(key: KeyCode) -> String {
+    // krace ime za ekran
+    match key {
+        KeyCode::Escape => "Esc".to_string(),
+        KeyCode::Return => "Enter".to_string(),
+        KeyCode::Back => "Backspace".to_string(),
+        KeyCode::Equals => "+".to_string(), // na vecini tastatura je + na istom tasteru
+        KeyCode::Minus => "-".to_string(),
+        k => key_name(k).trim_start_matches("Key").to_string(),
+    }
+}
+
+// Tasteri za svaku akciju, jedna akcija moze da ima vise tastera (npr. strelice i WASD).
+// Cuvaju se u direktorijumu za korisnicke podatke, svaka linija fajla je "akcija taster taster ..."
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    keys: HashMap<Action, Vec<KeyCode>>,
+    path: PathBuf,
+}
+
+impl Bindings {
+    fn defaults() -> HashMap<Action, Vec<KeyCode>> {
+        let mut keys: HashMap<Action, Vec<KeyCode>> = HashMap::new();
+        keys.insert(Action::MoveUp, vec![KeyCode::Up, KeyCode::W]);
+        keys.insert(Action::MoveDown, vec![KeyCode::Down, KeyCode::S]);
+        keys.insert(Action::MoveLeft, vec![KeyCode::Left, KeyCode::A]);
+        keys.insert(Action::MoveRight, vec![KeyCode::Right, KeyCode::D]);
+        keys.insert(Action::Interact, vec![KeyCode::E]);
+        keys.insert(Action::Sneak, vec![KeyCode::LControl]);
+        keys.insert(Action::Sprint, vec![KeyCode::LShift]);
+        keys.insert(Action::Throw, vec![KeyCode::F]);
+        keys.insert(Action::Pause, vec![KeyCode::Escape]);
+        keys.insert(Action::TogglePaths, vec![KeyCode::F3]);
+        keys.insert(Action::ZoomIn, vec![KeyCode::Equals]);
+        keys.insert(Action::ZoomOut, vec![KeyCode::Minus]);
+        keys.insert(Action::ToggleFullscreen, vec![KeyCode::F11]);
+        keys
+    }
+
+    pub fn load(ctx: &Context) -> Self {
+        // akcije kojih nema u fajlu (ili ako fajl ne postoji) dobijaju podrazumevane tastere
+        let path = filesystem::user_data_dir(ctx).join(BINDINGS_FILENAME);
+        let mut keys = Bindings::defaults();
+        if let Ok(text) = fs::read_to_string(&path) {
+            for line in text.lines() {
+                let mut fields = line.split(' ');
+                let action = match fields.next().and_then(|name| Action::ALL.iter().cloned().find(|a| a.name() == name)) {
+                    Some(action) => action,
+                    None => continue,
+                };
+                let action_keys: Vec<KeyCode> = fields.filter_map(key_from_name).collect();
+                if !action_keys.is_empty() {
+                    keys.insert(action, action_keys);
+                }
+            }
+        }
+        Bindings {
+            keys: keys,
+            path: path,
+        }
+    }
+
+    pub fn save(&self) -> GameResult<()> {
+        let mut text = String::new();
+        for action in Action::ALL.iter() {
+            let names: Vec<String> = self.keys(*action).iter().map(|k| key_name(*k)).collect();
+            text.push_str(&format!("{} {}\n", action.name(), names.join(" ")));
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, text)?;
+        Ok(())
+    }
+
+    pub fn reset(&mut self) {
+        self.keys = Bindings::defaults();
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        match self.keys.get(&action) {
+            Some(keys) => keys,
+            None => &[],
+        }
+    }
+
+    pub fn pressed(&self, ctx: &Context, action: Action) -> bool {
+        // da li je bilo koji taster akcije trenutno pritisnut
+        self.keys(action).iter().any(|k| input::keyboard::is_key_pressed(ctx, *k))
+    }
+
+    pub fn actions(&self, key: KeyCode) -> Vec<Action> {
+        // akcije koje pokrece taster, za key_down_event
+        Action::ALL.iter().cloned().filter(|a| self.keys(*a).contains(&key)).collect()
+    }
+
+    pub fn toggle(&mut self, action: Action, key: KeyCode) {
+        // taster koji akcija vec ima se skida (osim poslednjeg), inace se dodaje i skida
+        // sa drugih akcija, osim ako im je to jedini taster
+        let has_key = self.keys(action).contains(&key);
+        if has_key {
+            if self.keys(action).len() > 1 {
+                self.keys.entry(action).or_insert_with(Vec::new).retain(|k| *k != key);
+            }
+            return
+        }
+        for other in self.keys.values_mut() {
+            if other.len() > 1 {
+                other.retain(|k| *k != key);
+            }
+        }
+        self.keys.entry(action).or_insert_with(Vec::new).push(key);
+    }
+
+    pub fn describe(&self, action: Action) -> String {
+        let names: Vec<String> = self.keys(action).iter().map(|k| display_name(*k)).collect();
+        names.join(", ")
+    }
+
+    fn movement(&self) -> String {
+        // tasteri za kretanje u grupama, npr. "Arrows or WASD"
+        let directions = [Action::MoveUp, Action::MoveLeft, Action::MoveDown, Action::MoveRight];
+        let count = directions.iter().map(|a| self.keys(*a).len()).max().unwrap_or(0);
+        let mut groups: Vec<String> = Vec::new();
+        for i in 0..count {
+            let group: Vec<KeyCode> = directions.iter().filter_map(|a| self.keys(*a).get(i).cloned()).collect();
+            if group == [KeyCode::Up, KeyCode::Left, KeyCode::Down, KeyCode::Right] {
+                groups.push("Arrows".to_string());
+            } else {
+                groups.push(group.iter().map(|k| display_name(*k)).collect::<Vec<String>>().join(""));
+            }
+        }
+        groups.join(" or ")
+    }
+
+    pub fn help_text(&self) -> String {
+        // tekst za glavni meni, uvek po trenutnim tasterima
+        format!("Controls: {} to move,\n\n  {} to exit, {} to pause", self.movement(),
+                self.describe(Action::Interact).replace(", ", "/"), self.describe(Action::Pause).replace(", ", "/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> Bindings {
+        Bindings { keys: Bindings::defaults(), path: PathBuf::new() }
+    }
+
+    #[test]
+    fn toggle_adds_and_removes_keys() {
+        let mut bindings = defaults();
+        bindings.toggle(Action::Interact, KeyCode::Return);
+        assert_eq!(bindings.keys(Action::Interact), &[KeyCode::E, KeyCode::Return]);
+        bindings.toggle(Action::Interact, KeyCode::E);
+        assert_eq!(bindings.keys(Action::Interact), &[KeyCode::Return]);
+        // poslednji taster akcije ostaje
+        bindings.toggle(Action::Interact, KeyCode::Return);
+        assert_eq!(bindings.keys(Action::Interact), &[KeyCode::Return]);
+    }
+
+    #[test]
+    fn toggle_moves_key_only_from_actions_with_spare_keys() {
+        let mut bindings = defaults();
+        bindings.toggle(Action::Throw, KeyCode::W);
+        assert_eq!(bindings.keys(Action::MoveUp), &[KeyCode::Up]);
+        assert_eq!(bindings.actions(KeyCode::W), vec![Action::Throw]);
+        // Sprint ima samo LShift, pa ga deli sa Sneak umesto da ostane bez tastera
+        bindings.toggle(Action::Sneak, KeyCode::LShift);
+        assert_eq!(bindings.keys(Action::Sprint), &[KeyCode::LShift]);
+        assert_eq!(bindings.actions(KeyCode::LShift), vec![Action::Sneak, Action::Sprint]);
+    }
+
+    #[test]
+    fn reset_restores_defaults() {
+        let mut bindings = defaults();
+        bindings.toggle(Action::Pause, KeyCode::P);
+        bindings.toggle(Action::Pause, KeyCode::Escape);
+        assert_eq!(bindings.keys(Action::Pause), &[KeyCode::P]);
+        bindings.reset();
+        assert_eq!(bindings.keys(Action::Pause), &[KeyCode::Escape]);
+        assert_eq!(bindings.keys(Action::MoveUp), &[KeyCode::Up, KeyCode::W]);
+    }
+}
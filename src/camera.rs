@@ -0,0 +1,88 @@
+use ggez::{graphics, mint};
+use na::Vector2;
+
+const DEAD_ZONE: (f32, f32, f32, f32) = (200.0, 200.0, 240.0, 80.0); // deo ekrana (x, y, sirina, visina) u kom igrac ne pomera kameru
+const SMOOTHING: f32 = 0.25; // koliki deo puta do cilja kamera predje u jednom ticku, 1 je bez kasnjenja
+const MARGIN: f32 = 32.0; // koliko se van ivice mape sme videti
+const HUD_HEIGHT: f32 = 48.0; // kamen sa rezultatom pokriva dno ekrana, pa se ispod mape vidi malo vise
+pub const ZOOM_LEVELS: [f32; 3] = [0.5, 1.0, 2.0]; // 2 znaci da je sve dva puta vece
+
+// Sve u simulaciji je u koordinatama sveta i nikad se ne pomera zbog skrolovanja.
+// Kamera samo pamti koji deo sveta se vidi, a renderer od svake pozicije oduzima njenu poziciju
+#[derive(Debug, Clone)]
+pub struct Camera {
+    pub pos: mint::Point2<f32>, // gornji levi ugao ekrana u koordinatama sveta
+    pub prev_pos: mint::Point2<f32>, // pozicija pre poslednjeg ticka, za crtanje izmedju dva ticka
+    pub zoom: f32, // jedan od ZOOM_LEVELS, Gameplay ga prepisuje iz Renderer::zoom pre svakog ticka
+    view: mint::Point2<f32>, // velicina platna, u svetu se vidi view/zoom
+    bounds: graphics::Rect, // mapa sa marginama, kamera ne izlazi van nje
+}
+
+impl Camera {
+    pub fn new(view: (f32, f32), map: graphics::Rect, target: mint::Point2<f32>) -> Self {
+        let mut camera = Camera {
+            pos: mint::Point2 { x: 0.0, y: 0.0 },
+            prev_pos: mint::Point2 { x: 0.0, y: 0.0 },
+            zoom: 1.0,
+            view: mint::Point2 { x: view.0, y: view.1 },
+            bounds: graphics::Rect::new(map.x - MARGIN, map.y - MARGIN, map.w + 2.0*MARGIN, map.h + 2.0*MARGIN + HUD_HEIGHT),
+        };
+        camera.snap(target);
+        camera
+    }
+
+    fn clamp_axis(pos: f32, start: f32, size: f32, view: f32) -> f32 {
+        // mapa manja od ekrana se centrira
+        if size <= view {
+            start - (view - size)/2.0
+        } else {
+            pos.max(start).min(start + size - view)
+        }
+    }
+
+    fn goal(&self, target: mint::Point2<f32>) -> mint::Point2<f32> {
+        // najmanji pomeraj koji vraca metu u mrtvu zonu, pa jos da ne izadje van mape.
+        // Mrtva zona je zadata na ekranu, pa je u svetu manja sto je zoom veci
+        let (zone_x, zone_y, zone_w, zone_h) = (DEAD_ZONE.0/self.zoom, DEAD_ZONE.1/self.zoom, DEAD_ZONE.2/self.zoom, DEAD_ZONE.3/self.zoom);
+        let mut goal = self.pos;
+        if target.x < goal.x + zone_x {
+            goal.x = target.x - zone_x;
+        } else if target.x > goal.x + zone_x + zone_w {
+            goal.x = target.x - zone_x - zone_w;
+        }
+        if target.y < goal.y + zone_y {
+            goal.y = target.y - zone_y;
+        } else if target.y > goal.y + zone_y + zone_h {
+            goal.y = target.y - zone_y - zone_h;
+        }
+        mint::Point2 {
+            x: Camera::clamp_axis(goal.x, self.bounds.x, self.bounds.w, self.view.x/self.zoom),
+            y: Camera::clamp_axis(goal.y, self.bounds.y, self.bounds.h, self.view.y/self.zoom),
+        }
+    }
+
+    pub fn follow(&mut self, target: mint::Point2<f32>) {
+        // jedan tick, kamera se priblizava cilju umesto da skoci na njega
+        self.prev_pos = self.pos;
+        let goal = self.goal(target);
+        self.pos.x += (goal.x - self.pos.x)*SMOOTHING;
+        self.pos.y += (goal.y - self.pos.y)*SMOOTHING;
+    }
+
+    pub fn snap(&mut self, target: mint::Point2<f32>) {
+        // odmah na cilj, npr. na pocetku nivoa ili kad se nastavlja sacuvana igra
+        self.pos = self.goal(target);
+        self.prev_pos = self.pos;
+    }
+
+    pub fn settle(&mut self) {
+        self.prev_pos = self.pos;
+    }
+
+    pub fn offset(&self, alpha: f32) -> Vector2<f32> {
+        // sta se dodaje poziciji u svetu da bi se dobila pozicija na ekranu
+        let x = self.prev_pos.x + (self.pos.x - self.prev_pos.x)*alpha;
+        let y = self.prev_pos.y + (self.pos.y - self.prev_pos.y)*alpha;
+        Vector2::new(-x, -y)
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::Path;
+use ggez::{filesystem, Context, GameResult};
+
+use crate::level::{LevelError, read_level_file};
+
+pub const CAMPAIGN_FILENAME: &str = "/levels/campaign.txt";
+
+#[derive(Debug, Clone)]
+pub struct CampaignLevel {
+    pub name: String, // ime koje se prikazuje na ekranu
+    pub file: String, // putanja do fajla nivoa u resources
+}
+
+// Spisak nivoa koji se igraju redom, zlato se prenosi iz nivoa u nivo
+#[derive(Debug, Clone)]
+pub struct Campaign {
+    pub levels: Vec<CampaignLevel>,
+}
+
+impl Campaign {
+    pub fn parse(file_name: &str, text: &str) -> Result<Self, LevelError> {
+        // svaka linija je "fajl ime nivoa", fajl je relativan u odnosu na /levels/
+        let mut levels: Vec<CampaignLevel> = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut parts = line.splitn(2, ' ');
+            let file = parts.next().unwrap_or("");
+            let name = parts.next().unwrap_or("").trim();
+            if file.is_empty() || name.is_empty() {
+                return Err(LevelError::new(file_name, i+1, 1,
+                                           "campaign: expected a level file followed by the level name".to_string()));
+            }
+            levels.push(CampaignLevel {
+                name: name.to_string(),
+                file: format!("/levels/{}", file),
+            });
+        }
+        if levels.is_empty() {
+            return Err(LevelError::new(file_name, 1, 1, "campaign: no levels listed".to_string()));
+        }
+        Ok(Campaign {
+            levels: levels,
+        })
+    }
+
+    pub fn load(ctx: &mut Context, file_name: &str) -> GameResult<Self> {
+        let text = read_level_file(ctx, file_name)?;
+        Ok(Campaign::parse(file_name, &text)?)
+    }
+
+    pub fn name_of(&self, level_file: &str) -> String {
+        // ime iz kampanje, a za nivo koji nije u kampanji ime fajla bez ekstenzije
+        match self.levels.iter().find(|l| l.file == level_file) {
+            Some(l) => l.name.clone(),
+            None => Path::new(level_file).file_stem().and_then(|s| s.to_str()).unwrap_or(level_file).to_string(),
+        }
+    }
+
+    pub fn from_level(&self, level_file: &str) -> Campaign {
+        // igra koja pocinje od izabranog nivoa: ostatak kampanje ako je nivo u njoj, inace samo taj nivo
+        match self.levels.iter().position(|l| l.file == level_file) {
+            Some(i) => Campaign { levels: self.levels[i..].to_vec() },
+            None => Campaign { levels: vec![CampaignLevel { name: self.name_of(level_file), file: level_file.to_string() }] },
+        }
+    }
+
+    pub fn level_files(&self, ctx: &mut Context) -> GameResult<Vec<String>> {
+        // svi nivoi iz resources/levels/, prvo oni iz kampanje pa ostali po imenu
+        let mut files: Vec<String> = self.levels.iter().map(|l| l.file.clone()).collect();
+        let mut others: Vec<String> = filesystem::read_dir(ctx, "/levels")?
+            .filter(|p| p.extension().map_or(false, |e| e == "txt"))
+            .filter_map(|p| p.file_name().and_then(|n| n.to_str()).map(|n| format!("/levels/{}", n)))
+            .filter(|f| f != CAMPAIGN_FILENAME && !files.contains(f))
+            .collect();
+        others.sort();
+        others.dedup(); // isti fajl moze da postoji i u resources direktorijumu i u zip arhivi
+        files.append(&mut others);
+        Ok(files)
+    }
+}
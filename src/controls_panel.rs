@@ -0,0 +1,232 @@
+use std::collections::VecDeque;
+use ggez::*;
+use ggez::audio::SoundSource;
+
+use crate::bindings::{self, Action, Bindings};
+use crate::scene::{Scene, Shared, Transition};
+use crate::screen;
+use crate::ui::{self, MenuInput};
+
+const PANEL_OFFSET: f32 = 390.0; // isto kao u LevelSelect
+const ROW_HEIGHT: f32 = 21.0; // 13 redova mora da stane iznad Defaults i Back
+
+// Panel za promenu tastera, otvara se iz opcija u pauzi. Klik na akciju pa taster:
+// taster koji akcija nema se dodaje, a taster koji vec ima se skida
+pub struct ControlsPanel {
+    offset: f32,
+    stone_pos: mint::Point2<f32>,
+    title: graphics::Text,
+    title_pos: mint::Point2<f32>,
+    rows: Vec<graphics::Text>, // jedan red za svaku akciju iz Action::ALL
+    rows_pos: mint::Point2<f32>,
+    defaults: graphics::Text,
+    defaults_pos: mint::Point2<f32>,
+    back: graphics::Text,
+    back_pos: mint::Point2<f32>,
+    font: graphics::Font,
+    stone: graphics::Image,
+    stone_sound: audio::Source,
+    capturing: Option<Action>, // akcija koja ceka taster
+    pending_key: Option<event::KeyCode>, // taster pritisnut dok se cekalo, obradjuje se u updateu
+    was_pressed: bool,
+    closing: bool,
+    focus: Option<usize>, // red koji je zut: akcije, pa Defaults i Back
+    menu_input: VecDeque<MenuInput>, // sve sto je stiglo od proslog updatea, obradjuje se redom
+    last_mouse: mint::Point2<f32>,
+}
+
+impl ControlsPanel {
+    pub fn new(ctx: &mut Context, bindings: &Bindings) -> GameResult<Self> {
+        let font_celtknot = graphics::Font::new(ctx, "/fonts/Celtknot.ttf")?;
+        let mut title = graphics::Text::new("Controls");
+        title.set_font(font_celtknot, graphics::Scale::uniform(40.0));
+        let mut defaults = graphics::Text::new("Defaults");
+        defaults.set_font(font_celtknot, graphics::Scale::uniform(30.0));
+        let mut back = graphics::Text::new("Back");
+        back.set_font(font_celtknot, graphics::Scale::uniform(30.0));
+        let mut stone_sound = audio::Source::new(ctx, "/sounds/stone_short.mp3")?;
+        stone_sound.play_detached()?;
+
+        let mut panel = ControlsPanel {
+            offset: PANEL_OFFSET,
+            stone_pos: mint::Point2 { x: 40.0, y: 40.0 },
+            title: title,
+            title_pos: mint::Point2 { x: 100.0, y: 70.0 },
+            rows: Vec::new(),
+            rows_pos: mint::Point2 { x: 100.0, y: 120.0 },
+            defaults: defaults,
+            defaults_pos: mint::Point2 { x: 180.0, y: 400.0 },
+            back: back,
+            back_pos: mint::Point2 { x: 360.0, y: 400.0 },
+            font: font_celtknot,
+            stone: graphics::Image::new(ctx, "/images/user_interface.png")?,
+            stone_sound: stone_sound,
+            capturing: None,
+            pending_key: None,
+            was_pressed: true,
+            closing: false,
+            focus: None,
+            menu_input: VecDeque::new(),
+            last_mouse: screen::mouse_position(ctx),
+        };
+        panel.update_rows(bindings);
+        Ok(panel)
+    }
+
+    fn update_rows(&mut self, bindings: &Bindings) {
+        self.rows.clear();
+        for action in Action::ALL.iter() {
+            let keys = if self.capturing == Some(*action) { "press a key (Esc cancels)".to_string() } else { bindings.describe(*action) };
+            let mut row = graphics::Text::new(format!("{}: {}", action.label(), keys));
+            row.set_font(self.font, graphics::Scale::uniform(20.0));
+            self.rows.push(row);
+        }
+    }
+
+    fn row(&self, i: usize) -> mint::Point2<f32> {
+        mint::Point2 { x: self.rows_pos.x, y: self.rows_pos.y + i as f32*ROW_HEIGHT }
+    }
+
+    fn shifted(&self, pos: mint::Point2<f32>) -> mint::Point2<f32> {
+        mint::Point2 { x: pos.x, y: pos.y + self.offset }
+    }
+
+    fn hovered_row(&self, ctx: &mut Context) -> Option<usize> {
+        // rows.len() je Defaults, a rows.len()+1 je Back
+        if let Some(i) = (0..self.rows.len()).find(|&i| ui::hovered(ctx, &self.rows[i], self.row(i))) {
+            Some(i)
+        } else if ui::hovered(ctx, &self.defaults, self.defaults_pos) {
+            Some(self.rows.len())
+        } else if ui::hovered(ctx, &self.back, self.back_pos) {
+            Some(self.rows.len() + 1)
+        } else {
+            None
+        }
+    }
+
+    fn close(&mut self) {
+        if !self.closing {
+            self.closing = true;
+            self.stone_sound.play_detached().unwrap();
+        }
+    }
+
+    fn activate(&mut self, shared: &mut Shared, i: usize) -> GameResult<()> {
+        // isto za klik misem i za Enter ili dugme A; akciji se tasteri i dalje dodaju sa tastature
+        if i < self.rows.len() {
+            self.capturing = Some(Action::ALL[i]);
+        } else if i == self.rows.len() {
+            shared.bindings.reset();
+            shared.bindings.save()?;
+            self.capturing = None;
+        } else {
+            self.close();
+        }
+        self.update_rows(&shared.bindings);
+        Ok(())
+    }
+}
+
+impl Scene for ControlsPanel {
+    fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Transition> {
+        if self.closing {
+            self.offset += 5.0;
+            if self.offset >= PANEL_OFFSET {
+                return Ok(Transition::Pop)
+            }
+            return Ok(Transition::Stay)
+        }
+        if self.offset > 0.0 {
+            self.offset -= 5.0;
+            return Ok(Transition::Stay)
+        }
+        while let Some(menu_input) = self.menu_input.pop_front() {
+            // dok se ceka taster, strelice i Enter idu akciji, a Back (Esc ili dugme B) samo odustaje
+            let count = self.rows.len() + 2;
+            match menu_input {
+                MenuInput::Back if self.capturing.is_some() => {
+                    self.capturing = None;
+                    self.pending_key = None;
+                    self.update_rows(&shared.bindings);
+                },
+                _ if self.capturing.is_some() => (),
+                MenuInput::Up => self.focus = Some(self.focus.map_or(count - 1, |f| (f + count - 1) % count)),
+                MenuInput::Down => self.focus = Some(self.focus.map_or(0, |f| (f + 1) % count)),
+                MenuInput::Confirm => if let Some(i) = self.focus {
+                    self.activate(shared, i)?;
+                },
+                MenuInput::Back => self.close(),
+                _ => (),
+            }
+        }
+        if let (Some(action), Some(key)) = (self.capturing, self.pending_key.take()) {
+            if key != event::KeyCode::Escape && bindings::is_bindable(key) {
+                shared.bindings.toggle(action, key);
+                shared.bindings.save()?;
+            }
+            self.capturing = None;
+            self.update_rows(&shared.bindings);
+        }
+        let pressed = input::mouse::button_pressed(ctx, input::mouse::MouseButton::Left);
+        let clicked = pressed && !self.was_pressed;
+        self.was_pressed = pressed;
+
+        let hovered = self.hovered_row(ctx);
+        let mouse_pos = screen::mouse_position(ctx);
+        if mouse_pos != self.last_mouse {
+            // mis se pomerio, fokus prati mis
+            self.last_mouse = mouse_pos;
+            self.focus = hovered;
+        }
+        if clicked {
+            if let Some(i) = hovered {
+                self.activate(shared, i)?;
+            }
+        }
+        for i in 0..self.rows.len() {
+            let on = self.focus == Some(i) || self.capturing == Some(Action::ALL[i]);
+            ui::highlight(&mut self.rows[i], on);
+        }
+        let on = self.focus == Some(self.rows.len());
+        ui::highlight(&mut self.defaults, on);
+        let on = self.focus == Some(self.rows.len() + 1);
+        ui::highlight(&mut self.back, on);
+        Ok(Transition::Stay)
+    }
+
+    fn draw(&self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<()> {
+        graphics::draw(ctx, &self.stone, graphics::DrawParam::new().dest(self.shifted(self.stone_pos)))?;
+        graphics::draw(ctx, &self.title, graphics::DrawParam::new().dest(self.shifted(self.title_pos)))?;
+        for (i, row) in self.rows.iter().enumerate() {
+            graphics::draw(ctx, row, graphics::DrawParam::new().dest(self.shifted(self.row(i))))?;
+        }
+        graphics::draw(ctx, &self.defaults, graphics::DrawParam::new().dest(self.shifted(self.defaults_pos)))?;
+        graphics::draw(ctx, &self.back, graphics::DrawParam::new().dest(self.shifted(self.back_pos)))?;
+        Ok(())
+    }
+
+    fn key_down(&mut self, keycode: event::KeyCode) {
+        if self.capturing.is_some() {
+            self.pending_key = Some(keycode);
+        }
+    }
+
+    fn action(&mut self, action: Action) {
+        // Pause zatvara panel, osim ako se bas ceka taster (tada Esc samo odustaje)
+        if action == Action::Pause && self.capturing.is_none() {
+            self.close();
+        }
+    }
+
+    fn menu(&mut self, input: MenuInput) {
+        self.menu_input.push_back(input);
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn pauses_music(&self) -> bool {
+        true
+    }
+}
@@ -1,67 +1,228 @@
+use std::collections::VecDeque;
 use ggez::*;
 use ggez::audio::SoundSource;
 
+use crate::high_scores::{self, HighScore};
+use crate::main_menu::MainMenu;
+use crate::scene::{Scene, Shared, Transition};
+use crate::score::HeistReport;
+use crate::screen;
+use crate::ui::{self, MenuInput};
+
+const LINE_HEIGHT: f32 = 26.0; // razmak izmedju redova izvestaja
+const SLIDE: f32 = 390.0; // isto kao u MainMenu
+const TALLY_TICKS: i32 = 8; // na koliko tickova se pojavljuje sledeci red izvestaja
+const INITIALS_CHARS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789"; // slova koja se biraju D-padom
+
+// Kako se partija zavrsila, od toga zavisi naslov
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ending {
+    Caught,
+    LevelComplete, // partija od jednog nivoa
+    RunComplete, // predjen poslednji nivo, ali partija nije pocela od prvog nivoa kampanje
+    CampaignComplete,
+}
+
 pub struct GameOver {
     text1_pos: mint::Point2<f32>,
     text2_pos: mint::Point2<f32>,
     text3_pos: mint::Point2<f32>,
+    text4_pos: mint::Point2<f32>,
+    text5_pos: mint::Point2<f32>,
     stone_pos: mint::Point2<f32>,
     text1: graphics::Text,
-    text2: graphics::Text,
+    text2: Vec<graphics::Text>, // redovi izvestaja, crtaju se od text2_pos na dole
+    shown_lines: usize, // koliko redova izvestaja je vec prikazano
+    tally_ticks: i32,
     text3: graphics::Text,
+    text4: graphics::Text,
+    text5: graphics::Text,
     stone: graphics::Image,
     final_text1_pos: mint::Point2<f32>,
     final_text2_pos: mint::Point2<f32>,
     final_text3_pos: mint::Point2<f32>,
+    final_text4_pos: mint::Point2<f32>,
+    final_text5_pos: mint::Point2<f32>,
     final_stone_pos: mint::Point2<f32>,
     orig_text1_pos: mint::Point2<f32>,
     orig_text2_pos: mint::Point2<f32>,
     orig_text3_pos: mint::Point2<f32>,
+    orig_text4_pos: mint::Point2<f32>,
+    orig_text5_pos: mint::Point2<f32>,
     orig_stone_pos: mint::Point2<f32>,
     play_again: bool,
     stone_sound: audio::Source,
+    tally_sound: audio::Source,
+    font: graphics::Font,
+    entering: bool, // igrac upisuje inicijale za novi rekord
+    initials_input: String,
+    initials: Option<String>, // upisani inicijali, rekord se cuva u sledecem updateu
+    results: Vec<(String, i32, u32)>, // tabela, zlato i trajanje za rezultate koji ulaze u tabele
+    focused: bool, // "Play again" je zut, bira se misem, strelicama ili sa kontrolera
+    menu_input: VecDeque<MenuInput>, // sve sto je stiglo od proslog updatea, obradjuje se redom
+    last_mouse: mint::Point2<f32>,
 
 }
 
 impl GameOver {
-    pub fn new(ctx: &mut Context, score: i32, won: bool) -> GameResult<Self> {
-        let game_over_str = match won {
-            true => format!("You Won"),
-            false => format!("Game Over"),
+    pub fn new(ctx: &mut Context, level_name: &str, report: &HeistReport, score: i32, seed: u64, ending: Ending, results: Vec<(String, i32, u32)>) -> GameResult<Self> {
+        // ako je results prazan nema novog rekorda
+        let new_record = !results.is_empty();
+        let game_over_str = match ending {
+            Ending::Caught => "Game Over",
+            Ending::LevelComplete => "Level Complete",
+            Ending::RunComplete => "Run Complete",
+            Ending::CampaignComplete => "Campaign Complete",
         };
         let mut game_over_text = graphics::Text::new(game_over_str);
-        let mut high_score_text = graphics::Text::new(format!("Gold collected: {}", score));
+        let mut report_lines: Vec<String> = vec![
+            format!("Gold collected: {} / {}", report.gold_collected, report.gold_available),
+            format!("Time: {:02}:{:02}", report.seconds()/60, report.seconds()%60),
+            format!("Times spotted: {}", report.times_spotted),
+            format!("Guards evaded: {}", report.guards_evaded),
+        ];
+        for bonus in report.bonuses.iter() {
+            report_lines.push(format!("{}: +{}", bonus.name, bonus.gold));
+        }
+        report_lines.push(format!("Total gold: {}", score));
         let mut play_again_text = graphics::Text::new("Play again");
+        let mut seed_text = graphics::Text::new(format!("{}     Seed {}", level_name, seed));
         let font_celtknot = graphics::Font::new(ctx, "/fonts/Celtknot.ttf").unwrap();
         game_over_text.set_font(font_celtknot, graphics::Scale::uniform(40.0));
-        high_score_text.set_font(font_celtknot, graphics::Scale::uniform(40.0));
+        let mut report_text: Vec<graphics::Text> = Vec::new();
+        for line in report_lines {
+            let mut text = graphics::Text::new(line);
+            text.set_font(font_celtknot, graphics::Scale::uniform(24.0));
+            report_text.push(text);
+        }
         play_again_text.set_font(font_celtknot, graphics::Scale::uniform(30.0));
+        seed_text.set_font(font_celtknot, graphics::Scale::uniform(20.0));
+        let record_text = GameOver::record_text(font_celtknot, new_record, "");
         let mut stone_sound = audio::Source::new(ctx, "/sounds/stone_short.mp3").unwrap();
         stone_sound.play_detached()?;
+        let stone = graphics::Image::new(ctx, "/images/user_interface.png").unwrap();
+
+        // polozaji su na platnu, kao u MainMenu. Izvestaj i poruka o rekordu ostaju levo
+        // poravnati jer im se tekst menja
+        let final_text1_pos = mint::Point2 { x: screen::centered_x(ctx, &game_over_text), y: 55.0 };
+        let final_text2_pos = mint::Point2 { x: 140.0, y: 110.0 };
+        let final_text3_pos = mint::Point2 { x: screen::centered_x(ctx, &play_again_text), y: 392.0 };
+        let final_text4_pos = mint::Point2 { x: screen::centered_x(ctx, &seed_text), y: 440.0 };
+        let final_text5_pos = mint::Point2 { x: 120.0, y: 350.0 };
+        let final_stone_pos = mint::Point2 { x: (screen::VIRTUAL_SIZE.0 - stone.width() as f32)/2.0, y: 40.0 };
+        let below = |p: mint::Point2<f32>| mint::Point2 { x: p.x, y: p.y + SLIDE };
 
         Ok(GameOver {
-            text1_pos: mint::Point2 { x: 220.0, y: 542.0 },
-            text2_pos: mint::Point2 { x: 140.0, y: 642.0 },
-            text3_pos: mint::Point2 { x: 240.0, y: 742.0 },
-            stone_pos: mint::Point2 { x: 40.0, y: 430.0 },
+            text1_pos: below(final_text1_pos),
+            text2_pos: below(final_text2_pos),
+            text3_pos: below(final_text3_pos),
+            text4_pos: below(final_text4_pos),
+            text5_pos: below(final_text5_pos),
+            stone_pos: below(final_stone_pos),
             text1: game_over_text,
-            text2: high_score_text,
+            text2: report_text,
+            shown_lines: 0,
+            tally_ticks: 0,
             text3: play_again_text,
-            stone: graphics::Image::new(ctx, "/images/user_interface.png").unwrap(),
-            final_text1_pos: mint::Point2 { x: 220.0, y: 152.0 },
-            final_text2_pos: mint::Point2 { x: 140.0, y: 252.0 },
-            final_text3_pos: mint::Point2 { x: 240.0, y: 352.0 },
-            final_stone_pos: mint::Point2 { x: 40.0, y: 40.0 },
-            orig_text1_pos: mint::Point2 { x: 220.0, y: 542.0 },
-            orig_text2_pos: mint::Point2 { x: 140.0, y: 642.0 },
-            orig_text3_pos: mint::Point2 { x: 240.0, y: 742.0 },
-            orig_stone_pos: mint::Point2 { x: 40.0, y: 430.0 },
+            text4: seed_text,
+            text5: record_text,
+            stone: stone,
+            final_text1_pos: final_text1_pos,
+            final_text2_pos: final_text2_pos,
+            final_text3_pos: final_text3_pos,
+            final_text4_pos: final_text4_pos,
+            final_text5_pos: final_text5_pos,
+            final_stone_pos: final_stone_pos,
+            orig_text1_pos: below(final_text1_pos),
+            orig_text2_pos: below(final_text2_pos),
+            orig_text3_pos: below(final_text3_pos),
+            orig_text4_pos: below(final_text4_pos),
+            orig_text5_pos: below(final_text5_pos),
+            orig_stone_pos: below(final_stone_pos),
             play_again: false,
             stone_sound: stone_sound,
+            tally_sound: audio::Source::new(ctx, "/sounds/coins.wav")?,
+            font: font_celtknot,
+            entering: new_record,
+            initials_input: String::new(),
+            initials: None,
+            results: results,
+            focused: false,
+            menu_input: VecDeque::new(),
+            last_mouse: screen::mouse_position(ctx),
         })
     }
 
-    pub fn update(&mut self, ctx: &mut Context) -> bool {
+    fn confirm_initials(&mut self) {
+        if self.initials_input.is_empty() {
+            return
+        }
+        self.entering = false;
+        self.initials = Some(self.initials_input.clone());
+        let mut saved = graphics::Text::new(format!("Record saved: {}", self.initials_input));
+        saved.set_font(self.font, graphics::Scale::uniform(30.0));
+        self.text5 = saved;
+    }
+
+    fn cycle_initial(&mut self, step: i32) {
+        // D-pad gore/dole menja poslednje slovo, kao na starim automatima
+        let chars: Vec<char> = INITIALS_CHARS.chars().collect();
+        let count = chars.len() as i32;
+        let next = match self.initials_input.pop() {
+            Some(c) => {
+                let i = chars.iter().position(|x| *x == c).unwrap_or(0) as i32;
+                chars[((i + step + count) % count) as usize]
+            },
+            None => chars[0],
+        };
+        self.initials_input.push(next);
+    }
+
+    fn initials_menu(&mut self, menu_input: MenuInput) {
+        // upis inicijala bez tastature za slova: gore/dole bira slovo, desno dodaje novo, levo brise
+        match menu_input {
+            MenuInput::Up => self.cycle_initial(1),
+            MenuInput::Down => self.cycle_initial(-1),
+            MenuInput::Right if self.initials_input.len() < 3 => self.initials_input.push('A'),
+            MenuInput::Left | MenuInput::Back => {
+                self.initials_input.pop();
+            },
+            MenuInput::Confirm => {
+                self.confirm_initials();
+                return
+            },
+            _ => (),
+        }
+        self.text5 = GameOver::record_text(self.font, true, &self.initials_input);
+    }
+
+    fn record_text(font: graphics::Font, new_record: bool, initials: &str) -> graphics::Text {
+        let mut text = match new_record {
+            true => graphics::Text::new(format!("New record! Initials: {}_", initials)),
+            false => graphics::Text::new(""),
+        };
+        text.set_font(font, graphics::Scale::uniform(30.0));
+        for fragment in text.fragments_mut() {
+            fragment.color = Some((208, 198, 29, 255).into());
+        }
+        text
+    }
+}
+
+impl Scene for GameOver {
+    fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Transition> {
+        if let Some(initials) = self.initials.take() {
+            let date = high_scores::today();
+            for (table, gold, ticks) in self.results.drain(..) {
+                shared.high_scores.insert(&table, HighScore {
+                    initials: initials.clone(),
+                    gold: gold,
+                    ticks: ticks,
+                    date: date.clone(),
+                });
+            }
+            shared.high_scores.save()?;
+        }
         if self.play_again == false {
             if self.text1_pos != self.final_text1_pos {
                 self.text1_pos.y -= 5.0;
@@ -72,30 +233,47 @@ impl GameOver {
             if self.text3_pos != self.final_text3_pos {
                 self.text3_pos.y -= 5.0;
             }
+            if self.text4_pos != self.final_text4_pos {
+                self.text4_pos.y -= 5.0;
+            }
+            if self.text5_pos != self.final_text5_pos {
+                self.text5_pos.y -= 5.0;
+            }
             if self.stone_pos != self.final_stone_pos {
                 self.stone_pos.y -= 5.0;
+            } else if self.shown_lines < self.text2.len() {
+                // kad kamen stigne gore, redovi izvestaja se pojavljuju jedan po jedan
+                self.tally_ticks += 1;
+                if self.tally_ticks >= TALLY_TICKS {
+                    self.tally_ticks = 0;
+                    self.shown_lines += 1;
+                    self.tally_sound.play_detached().unwrap();
+                }
+            }
+            let hovered = ui::hovered(ctx, &self.text3, self.text3_pos);
+            let mouse_position = screen::mouse_position(ctx);
+            if mouse_position != self.last_mouse {
+                // mis se pomerio, fokus prati mis
+                self.last_mouse = mouse_position;
+                self.focused = hovered;
             }
-            let mouse_position = input::mouse::position(ctx);
-            if (mouse_position.x >= self.text3_pos.x) && (mouse_position.x < self.text3_pos.x + (self.text3.dimensions(ctx).0 as f32)) &&
-                (mouse_position.y >= self.text3_pos.y) && (mouse_position.y < self.text3_pos.y + (self.text3.dimensions(ctx).1 as f32)) {
-                    for fragment in self.text3.fragments_mut() {
-                        // tip Text se sastoji od fragmenata
-                        // tj polja koji predstavljaju osobine Text-a
-                        // jedan od fragmenata je boja, koju ovde menjamo
-                        // ako se mis nalazi preko teksta "Play again"
-                        fragment.color = Some((208, 198, 29, 255).into());
-                    }
-                    if input::mouse::button_pressed(ctx, input::mouse::MouseButton::Left) {
-                        self.play_again = true;
-                        self.stone_sound.play_detached().unwrap();
-                    }
-                } else {
-                    for fragment in self.text3.fragments_mut() {
-                        fragment.color = Some([1.0, 1.0, 1.0, 1.0].into());
-                    }
-                    //boja ostaje ista ako se mis ne nalazi preko
-                    // teksta "Play again"
+            let mut confirmed = input::mouse::button_pressed(ctx, input::mouse::MouseButton::Left) && hovered;
+            while let Some(menu_input) = self.menu_input.pop_front() {
+                match menu_input {
+                    _ if self.entering => self.initials_menu(menu_input),
+                    MenuInput::Up | MenuInput::Down => self.focused = true, // jedina stavka
+                    MenuInput::Confirm => confirmed = confirmed || self.focused,
+                    MenuInput::Back => confirmed = true, // Esc se vraca u glavni meni, isto kao "Play again"
+                    _ => (),
                 }
+            }
+            // tekst "Play again" je zut kad je u fokusu
+            ui::highlight(&mut self.text3, self.focused);
+            if confirmed && !self.entering {
+                // dok se upisuju inicijali ne moze da se igra ponovo
+                self.play_again = true;
+                self.stone_sound.play_detached().unwrap();
+            }
         }
         else { // ako je pritisnuto "Play again" spustamo kamen
             if self.text1_pos != self.orig_text1_pos {
@@ -107,21 +285,52 @@ impl GameOver {
             if self.text3_pos != self.orig_text3_pos {
                 self.text3_pos.y += 5.0;
             }
+            if self.text4_pos != self.orig_text4_pos {
+                self.text4_pos.y += 5.0;
+            }
+            if self.text5_pos != self.orig_text5_pos {
+                self.text5_pos.y += 5.0;
+            }
             if self.stone_pos != self.orig_stone_pos {
                 self.stone_pos.y += 5.0;
             }
             if self.stone_pos == self.orig_stone_pos {
-                return false // prestajemo da updateujemo GameOver i pocinjemo igricu ispocetka
+                return Ok(Transition::Reset(Box::new(MainMenu::new(ctx, &shared.bindings)?))) // prestajemo da updateujemo GameOver i pocinjemo igricu ispocetka
             }
         }
-        true
+        Ok(Transition::Stay)
     }
 
-    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+    fn draw(&self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<()> {
         graphics::draw(ctx, &self.stone, graphics::DrawParam::new().dest(self.stone_pos))?;
         graphics::draw(ctx, &self.text1, graphics::DrawParam::new().dest(self.text1_pos))?;
-        graphics::draw(ctx, &self.text2, graphics::DrawParam::new().dest(self.text2_pos))?;
+        for (i, line) in self.text2.iter().take(self.shown_lines).enumerate() {
+            let pos = mint::Point2 { x: self.text2_pos.x, y: self.text2_pos.y + i as f32*LINE_HEIGHT };
+            graphics::draw(ctx, line, graphics::DrawParam::new().dest(pos))?;
+        }
         graphics::draw(ctx, &self.text3, graphics::DrawParam::new().dest(self.text3_pos))?;
+        graphics::draw(ctx, &self.text4, graphics::DrawParam::new().dest(self.text4_pos))?;
+        graphics::draw(ctx, &self.text5, graphics::DrawParam::new().dest(self.text5_pos))?;
         Ok(())
     }
+
+    fn text_input(&mut self, c: char) {
+        // do tri slova ili cifre, kao na starim automatima
+        if self.entering && c.is_ascii_alphanumeric() && self.initials_input.len() < 3 {
+            self.initials_input.push(c.to_ascii_uppercase());
+            self.text5 = GameOver::record_text(self.font, true, &self.initials_input);
+        }
+    }
+
+    fn menu(&mut self, input: MenuInput) {
+        self.menu_input.push_back(input);
+    }
+
+    fn key_down(&mut self, keycode: event::KeyCode) {
+        // Enter i strelice stizu kao MenuInput
+        if self.entering && keycode == event::KeyCode::Back {
+            self.initials_input.pop();
+            self.text5 = GameOver::record_text(self.font, true, &self.initials_input);
+        }
+    }
 }
@@ -0,0 +1,106 @@
+use ggez::event::{Axis, Button};
+use ggez::input::gamepad::GamepadId;
+
+use crate::bindings::Action;
+use crate::sim::STICK_STEPS;
+use crate::ui::MenuInput;
+
+const STICK_DEADZONE: f32 = 0.3; // manji otklon palice se ne racuna, palice retko stoje bas na nuli
+
+pub fn action(button: Button) -> Option<Action> {
+    // dugmad na kontroleru imaju iste akcije kao tasteri
+    match button {
+        Button::DPadUp => Some(Action::MoveUp),
+        Button::DPadDown => Some(Action::MoveDown),
+        Button::DPadLeft => Some(Action::MoveLeft),
+        Button::DPadRight => Some(Action::MoveRight),
+        Button::South => Some(Action::Interact),
+        Button::West => Some(Action::Throw),
+        Button::LeftTrigger => Some(Action::Sneak),
+        Button::RightTrigger => Some(Action::Sprint),
+        Button::Start => Some(Action::Pause),
+        _ => None,
+    }
+}
+
+pub fn menu_input(button: Button) -> Option<MenuInput> {
+    match button {
+        Button::DPadUp => Some(MenuInput::Up),
+        Button::DPadDown => Some(MenuInput::Down),
+        Button::DPadLeft => Some(MenuInput::Left),
+        Button::DPadRight => Some(MenuInput::Right),
+        Button::South => Some(MenuInput::Confirm),
+        Button::East => Some(MenuInput::Back),
+        _ => None,
+    }
+}
+
+// Jedan povezan kontroler
+#[derive(Debug, Clone)]
+struct Pad {
+    id: GamepadId,
+    buttons: Vec<Button>, // dugmad koja su trenutno pritisnuta
+    stick_x: f32, // leva palica, od -1 do 1
+    stick_y: f32, // na gore je pozitivno
+}
+
+// Stanje svih povezanih kontrolera zajedno, main.rs ga azurira iz gamepad dogadjaja
+#[derive(Debug, Clone, Default)]
+pub struct GamepadState {
+    pads: Vec<Pad>,
+}
+
+impl GamepadState {
+    fn pad(&mut self, id: GamepadId) -> &mut Pad {
+        // kontroler dobija stanje sa prvim dogadjajem
+        match self.pads.iter().position(|p| p.id == id) {
+            Some(i) => &mut self.pads[i],
+            None => {
+                self.pads.push(Pad { id: id, buttons: Vec::new(), stick_x: 0.0, stick_y: 0.0 });
+                self.pads.last_mut().unwrap()
+            },
+        }
+    }
+
+    pub fn button_down(&mut self, id: GamepadId, button: Button) {
+        let pad = self.pad(id);
+        if !pad.buttons.contains(&button) {
+            pad.buttons.push(button);
+        }
+    }
+
+    pub fn button_up(&mut self, id: GamepadId, button: Button) {
+        self.pad(id).buttons.retain(|b| *b != button);
+    }
+
+    pub fn axis(&mut self, id: GamepadId, axis: Axis, value: f32) {
+        let pad = self.pad(id);
+        match axis {
+            Axis::LeftStickX => pad.stick_x = value,
+            Axis::LeftStickY => pad.stick_y = value,
+            _ => (),
+        }
+    }
+
+    pub fn forget_disconnected<F: Fn(GamepadId) -> bool>(&mut self, connected: F) {
+        // ggez ne javlja kad se kontroler iskljuci, pa bi dugme drzano u tom trenutku ostalo pritisnuto
+        self.pads.retain(|p| connected(p.id));
+    }
+
+    pub fn stick(&self) -> (i32, i32) {
+        // otklon leve palice u stotim delovima, y na dole kao na ekranu. Mrtva zona se oduzima,
+        // pa brzina raste od nule, a zaokruzivanje cuva snimak partije istim pri ponovnom pustanju
+        let (x, y) = self.pads.iter().map(|p| (p.stick_x, -p.stick_y))
+            .fold((0.0, 0.0), |best: (f32, f32), s| if s.0.hypot(s.1) > best.0.hypot(best.1) { s } else { best });
+        let length = x.hypot(y);
+        if length < STICK_DEADZONE {
+            return (0, 0)
+        }
+        let scale = ((length - STICK_DEADZONE)/(1.0 - STICK_DEADZONE)).min(1.0)/length;
+        ((x*scale*STICK_STEPS).round() as i32, (y*scale*STICK_STEPS).round() as i32)
+    }
+
+    pub fn pressed(&self, wanted: Action) -> bool {
+        self.pads.iter().any(|p| p.buttons.iter().any(|b| action(*b) == Some(wanted)))
+    }
+}
@@ -0,0 +1,276 @@
+use ggez::*;
+use ggez::audio::SoundSource;
+
+use crate::bindings::{Action, Bindings};
+use crate::gamepad::GamepadState;
+use crate::campaign::{Campaign, CampaignLevel};
+use crate::game_over::{Ending, GameOver};
+use crate::high_scores::CAMPAIGN_TABLE;
+use crate::level;
+use crate::main_menu::MainMenu;
+use crate::pause_menu::PauseMenu;
+use crate::render::{self, Renderer};
+use crate::replay::{Playback, Recorder, Replay, ReplayStep};
+use crate::save_game::SaveGame;
+use crate::scene::{Scene, Shared, Signal, Transition};
+use crate::score::Score;
+use crate::sim::{Input, Outcome, Simulation};
+
+fn load_level(ctx: &mut Context, renderer: &mut Renderer, level: &CampaignLevel, seed: u64, gold: i32) -> GameResult<Simulation> {
+    // nova simulacija za nivo kampanje, gold je zlato skupljeno u prethodnim nivoima
+    let level_text = level::read_level_file(ctx, &level.file)?;
+    let mut sim = Simulation::new(&level.file, &level_text, renderer.tile_fraction(), seed)?;
+    sim.player.increase(gold);
+    // simulacija pravi kameru bez zuma, pa je ponovo postavljamo da prvi frejm ne bi skocio
+    sim.camera.zoom = renderer.zoom;
+    sim.camera.snap(sim.player.pos);
+    renderer.load_treasures(ctx, &sim.castle_map)?;
+    Ok(sim)
+}
+
+fn read_input(ctx: &Context, bindings: &Bindings, gamepad: &GamepadState) -> Input {
+    // jedino mesto gde se citaju tastatura i kontroler za samu igru
+    let pressed = |action: Action| bindings.pressed(ctx, action) || gamepad.pressed(action);
+    let (stick_x, stick_y) = gamepad.stick();
+    Input {
+        up: pressed(Action::MoveUp),
+        down: pressed(Action::MoveDown),
+        left: pressed(Action::MoveLeft),
+        right: pressed(Action::MoveRight),
+        interact: pressed(Action::Interact),
+        sneak: pressed(Action::Sneak),
+        sprint: pressed(Action::Sprint),
+        throw: pressed(Action::Throw),
+        stick_x: stick_x,
+        stick_y: stick_y,
+    }
+}
+
+// Odakle dolazi unos: igrac za tastaturom ili snimak sa --replay. Nastavljena
+// sacuvana igra se ne snima, jer snimak uvek krece od pocetka nivoa
+enum Controls {
+    Keyboard(Option<Recorder>),
+    Replay(Playback),
+}
+
+// Scena same igre: jedna partija od izabranog nivoa do kraja kampanje
+pub struct Gameplay {
+    run: Campaign, // nivoi koji se igraju u ovoj partiji, od izabranog nivoa do kraja kampanje
+    level_index: usize, // koji nivo iz run se trenutno igra
+    carried_gold: i32, // zlato skupljeno u prethodnim nivoima ove partije
+    run_ticks: u32, // trajanje prethodnih nivoa ove partije
+    results: Vec<(String, i32, u32)>, // tabela, zlato i trajanje za rezultate ove partije
+    seed: u64, // isti seed vazi za sve nivoe jedne kampanje
+    sim: Simulation,
+    coin_sound: audio::Source,
+    pause_requested: bool, // Escape je pritisnut, pauza se otvara u sledecem updateu
+    controls: Controls,
+    quit_requested: bool, // igra je sacuvana iz pauze, sledeci update vraca u glavni meni
+}
+
+impl Gameplay {
+    pub fn new(ctx: &mut Context, shared: &mut Shared, level_file: Option<&str>) -> GameResult<Self> {
+        // bez izabranog nivoa igra se cela kampanja
+        let run = match level_file {
+            Some(file) => shared.campaign.from_level(file),
+            None => shared.campaign.clone(),
+        };
+        let seed = shared.next_seed();
+        let recorder = Recorder::start(ctx, seed, level_file)?;
+        let sim = load_level(ctx, &mut shared.renderer, &run.levels[0], seed, 0)?;
+        Gameplay::start(ctx, run, seed, sim, Controls::Keyboard(Some(recorder)))
+    }
+
+    pub fn resume(ctx: &mut Context, shared: &mut Shared, save: SaveGame) -> GameResult<Self> {
+        // partija sacuvana iz pauze: isti nivo i seed, pa se na njega prenese sacuvano stanje
+        let run = shared.campaign.from_level(&save.run_start);
+        if save.level_index >= run.levels.len() {
+            return Err(GameError::ResourceLoadError("saved game does not match the campaign".to_string()))
+        }
+        let mut sim = load_level(ctx, &mut shared.renderer, &run.levels[save.level_index], save.seed, save.carried_gold)?;
+        sim.restore(&save.sim)?;
+        let mut game = Gameplay::start(ctx, run, save.seed, sim, Controls::Keyboard(None))?;
+        game.level_index = save.level_index;
+        game.carried_gold = save.carried_gold;
+        game.run_ticks = save.run_ticks;
+        game.results = save.results;
+        SaveGame::delete(ctx)?;
+        Ok(game)
+    }
+
+    pub fn from_replay(ctx: &mut Context, shared: &mut Shared, replay: Replay) -> GameResult<Self> {
+        // partija koju vodi snimak, sa seedom i nivoom iz snimka
+        let run = match replay.level_file {
+            Some(ref file) => shared.campaign.from_level(file),
+            None => shared.campaign.clone(),
+        };
+        let seed = replay.seed;
+        let sim = load_level(ctx, &mut shared.renderer, &run.levels[0], seed, 0)?;
+        Gameplay::start(ctx, run, seed, sim, Controls::Replay(Playback::new(replay)))
+    }
+
+    fn start(ctx: &mut Context, run: Campaign, seed: u64, sim: Simulation, controls: Controls) -> GameResult<Self> {
+        Ok(Gameplay {
+            run: run,
+            level_index: 0,
+            carried_gold: 0,
+            run_ticks: 0,
+            results: Vec::new(),
+            seed: seed,
+            sim: sim,
+            coin_sound: audio::Source::new(ctx, "/sounds/coins.wav")?,
+            pause_requested: false,
+            controls: controls,
+            quit_requested: false,
+        })
+    }
+
+    fn snapshot(&self) -> SaveGame {
+        SaveGame {
+            run_start: self.run.levels[0].file.clone(),
+            level_index: self.level_index,
+            seed: self.seed,
+            carried_gold: self.carried_gold,
+            run_ticks: self.run_ticks,
+            results: self.results.clone(),
+            sim: self.sim.snapshot(),
+        }
+    }
+
+    fn record_progress(&mut self, shared: &mut Shared, completed: bool) -> GameResult<()> {
+        // nivo je zavrsen (pobegli smo ili smo uhvaceni), pamtimo napredak i rezultat za tabelu nivoa.
+        // Pusteni snimak se ne racuna u napredak ni u rezultate
+        if let Controls::Replay(_) = self.controls {
+            return Ok(())
+        }
+        let level_file = self.run.levels[self.level_index].file.clone();
+        // samo ovaj nivo: zlato i bonusi za bekstvo, bez zlata donetog iz prethodnih nivoa.
+        // Isti broj ide i u tabelu nivoa, pa izbor nivoa i Records prikazuju isti najbolji rezultat
+        let level_gold = self.sim.player.total() - self.carried_gold;
+        shared.progress.update(&level_file, level_gold, completed);
+        self.results.push((level_file, level_gold, self.sim.ticks));
+        shared.progress.save()
+    }
+
+    fn finish_run(&mut self, shared: &Shared) -> Vec<(String, i32, u32)> {
+        // kraj partije, vraca rezultate koji ulaze u tabele pa za njih treba pitati za inicijale
+        if self.run.levels[0].file == shared.campaign.levels[0].file && !self.results.is_empty() {
+            // tabela kampanje vazi samo za partije od prvog nivoa
+            self.results.push((CAMPAIGN_TABLE.to_string(), self.sim.player.total(), self.run_ticks + self.sim.ticks));
+        }
+        let mut results = std::mem::replace(&mut self.results, Vec::new());
+        results.retain(|(table, gold, ticks)| shared.high_scores.qualifies(table, *gold, *ticks));
+        results
+    }
+}
+
+impl Scene for Gameplay {
+    fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Transition> {
+        // jedan tick igre i prelaz na sledeci nivo ili kraj partije
+        if self.quit_requested {
+            return Ok(Transition::Reset(Box::new(MainMenu::new(ctx, &shared.bindings)?)))
+        }
+        if self.pause_requested {
+            self.pause_requested = false;
+            self.sim.settle(); // da igra ispod pauze ne podrhtava
+            return Ok(Transition::Push(Box::new(PauseMenu::new(ctx, shared.music_on)?)))
+        }
+        let input = match self.controls {
+            Controls::Keyboard(ref mut recorder) => {
+                let input = read_input(ctx, &shared.bindings, &shared.gamepad);
+                if let Some(recorder) = recorder {
+                    recorder.tick(&input)?;
+                }
+                input
+            },
+            Controls::Replay(ref mut playback) => match playback.next_step() {
+                Some(ReplayStep::Tick(input)) => input,
+                Some(ReplayStep::Restart) => {
+                    self.sim = load_level(ctx, &mut shared.renderer, &self.run.levels[self.level_index], self.seed, self.carried_gold)?;
+                    return Ok(Transition::Stay)
+                },
+                None => {
+                    // snimak je gotov, igra ostaje zamrznuta u poslednjem snimljenom ticku
+                    self.sim.settle();
+                    return Ok(Transition::Stay)
+                },
+            },
+        };
+        self.sim.camera.zoom = shared.renderer.zoom;
+        let events = self.sim.step(&input);
+        if events.gold_collected > 0 {
+            self.coin_sound.play_detached()?;
+        }
+        let level_name = self.run.levels[self.level_index].name.clone();
+        match self.sim.outcome {
+            Outcome::Caught => {
+                let report = self.sim.report(self.carried_gold);
+                self.record_progress(shared, false)?;
+                let results = self.finish_run(shared);
+                Ok(Transition::Replace(Box::new(GameOver::new(ctx, &level_name, &report, self.sim.player.total(), self.seed, Ending::Caught, results)?)))
+            },
+            Outcome::Escaped if self.level_index + 1 < self.run.levels.len() => {
+                // pobegli smo kroz vrata, prelazimo na sledeci nivo i nosimo zlato sa sobom
+                let report = self.sim.report(self.carried_gold);
+                self.sim.player.increase(report.bonus_total());
+                self.record_progress(shared, true)?;
+                self.level_index += 1;
+                self.carried_gold = self.sim.player.total();
+                self.run_ticks += self.sim.ticks;
+                self.sim = load_level(ctx, &mut shared.renderer, &self.run.levels[self.level_index], self.seed, self.carried_gold)?;
+                Ok(Transition::Stay)
+            },
+            Outcome::Escaped => {
+                let report = self.sim.report(self.carried_gold);
+                self.sim.player.increase(report.bonus_total());
+                self.record_progress(shared, true)?;
+                let results = self.finish_run(shared);
+                // kampanja je predjena samo ako je partija pocela od njenog prvog nivoa
+                let ending = if self.run.levels[0].file == shared.campaign.levels[0].file {
+                    Ending::CampaignComplete
+                } else if self.run.levels.len() == 1 {
+                    Ending::LevelComplete
+                } else {
+                    Ending::RunComplete
+                };
+                Ok(Transition::Replace(Box::new(GameOver::new(ctx, &level_name, &report, self.sim.player.total(), self.seed, ending, results)?)))
+            },
+            Outcome::Running => Ok(Transition::Stay),
+        }
+    }
+
+    fn draw(&self, ctx: &mut Context, shared: &mut Shared) -> GameResult<()> {
+        // mapa, vrata, zlato, igrac, strazari i score, izmedju poslednja dva ticka
+        let alpha = render::tick_fraction(ctx);
+        shared.renderer.draw(ctx, &self.sim, &self.run.levels[self.level_index].name, alpha)
+    }
+
+    fn action(&mut self, action: Action) {
+        if action == Action::Pause {
+            self.pause_requested = true;
+        }
+    }
+
+    fn signal(&mut self, ctx: &mut Context, shared: &mut Shared, signal: Signal) -> GameResult<()> {
+        match signal {
+            Signal::RestartLevel => {
+                // isti nivo ispocetka, sa zlatom koje je igrac doneo u njega.
+                // Snimak sam zna kad je nivo pokrenut ispocetka, pa ga pauza ne moze restartovati
+                match self.controls {
+                    Controls::Keyboard(Some(ref mut recorder)) => recorder.restart()?,
+                    Controls::Keyboard(None) => (),
+                    Controls::Replay(_) => return Ok(()),
+                }
+                self.sim = load_level(ctx, &mut shared.renderer, &self.run.levels[self.level_index], self.seed, self.carried_gold)?;
+            },
+            Signal::SaveAndQuit => {
+                // snimak sa --replay se ne cuva, samo se izlazi u meni
+                if let Controls::Keyboard(_) = self.controls {
+                    self.snapshot().save(ctx)?;
+                }
+                self.quit_requested = true;
+            },
+        }
+        Ok(())
+    }
+}
@@ -1,59 +1,141 @@
 use std::f32::consts::PI;
-use ggez::*;
-use na::{Vector2, Isometry2, Rotation2};
+use ggez::mint;
+use na::{Vector2, Isometry2, Rotation2, Point2};
+use ncollide2d::shape::Compound;
 use ncollide2d::world::{CollisionObjectHandle, CollisionWorld};
-use rand::{thread_rng, Rng};
+use ncollide2d::query::{Ray, RayCast};
+use rand::Rng;
+use rand::rngs::StdRng;
 
-use crate::anim::{Animation, Direction};
+use crate::anim::{Direction, Frames};
+use crate::noise::{Noise, NoiseKind};
+use crate::pathfinding::{Grid, TilePos};
+
+const VISION_RANGE: f32 = 64.0; // duzina vidnog polja, ista kao kod trougla iz Map::load
+const VISION_RAYS: usize = 12; // na koliko delova delimo vidno polje kad ga secemo zidovima
+const DETECTION_FILL: f32 = 1.0/30.0; // koliko se merac puni po ticku kad je igrac na ivici vidnog polja
+const DETECTION_DECAY: f32 = 1.0/60.0; // koliko se merac prazni po ticku kad igrac nije vidljiv
+const HEARING_FILL: f32 = 1.0/45.0; // koliko se merac puni po ticku dok strazar cuje korake, sporije nego kad vidi
+const INVESTIGATE_TICKS: i32 = 120; // koliko dugo (4 sekunde) strazar proverava mesto odakle je cuo zvuk
+const CHASE_SPD: f32 = 4.2; // strazar koji juri je brzi od igraca (4.0)
+const CATCH_DISTANCE: f32 = 20.0; // koliko blizu strazar u poteri mora da pridje da bi uhvatio igraca
+const SEARCH_TICKS: i32 = 150; // koliko dugo (5 sekundi) strazar trazi igraca oko mesta gde ga je poslednji put video
+const SEARCH_RADIUS: f32 = 48.0;
+
+// Stanja strazara. Stanje se menja samo u Guard::update
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GuardState {
+    Patrol,
+    Suspicious, // video je nesto, stoji i okrece se ka tome
+    Alert, // juri igraca
+    Search(i32), // trazi oko poslednje poznate pozicije, broj je preostalo vreme u tickovima
+    Investigate(i32), // cuo je nesto, ide do mesta zvuka i tamo stoji, broj je preostalo vreme u tickovima
+    Return, // vraca se na svoju rutu
+}
+
+// Kako strazar prolazi kroz tacke rute: u krug (1,2,3,1,2,3...) ili napred-nazad (1,2,3,2,1...)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RouteMode {
+    Loop,
+    PingPong,
+}
+
+// Jedna tacka rute strazara; koordinate su gornji levi ugao slike strazara u svetu
+#[derive(Debug, Clone)]
+pub struct Waypoint {
+    pub pos: mint::Point2<f32>,
+    pub wait: i32, // koliko tickova strazar stoji kad stigne
+    pub facing: Option<Vector2<f32>>, // na koju stranu gleda dok stoji
+}
+
+// Ono sto se o strazaru pamti u sacuvanoj igri
+#[derive(Debug, Clone)]
+pub struct GuardSnapshot {
+    pub pos: mint::Point2<f32>,
+    pub patrol: usize, // indeks tacke rute ka kojoj ide
+    pub patrol_step: i32,
+    pub rotation: f32, // ugao za koji je okrenut od pocetnog pravca (dole)
+    pub gave_up: i32,
+}
+
+// Koliko je uzbunjen strazar, redosled je bitan jer Map::update_guards vraca najvecu vrednost
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Alertness {
+    Calm,
+    Suspicious,
+    Searching,
+    Alert,
+    Caught,
+}
 
 #[derive(Debug)]
 pub struct Guard {
-    pos: mint::Point2<f32>,
+    pub pos: mint::Point2<f32>,
+    pub prev_pos: mint::Point2<f32>, // pozicija pre poslednjeg ticka, renderer crta izmedju nje i pos
     direction: Vector2<f32>,
-    final_direction: Vector2<f32>,
-    run_left: Animation,
-    run_right: Animation,
-    run_down: Animation,
-    run_up: Animation,
-    animation_state: Direction,
+    pub frames: Frames,
+    pub animation_state: Direction,
     spd: f32,
     turn_spd: f32,
-    rotation: Rotation2<f32>,
     total_rotation: f32,
     next_point: mint::Point2<f32>,
-    patrol_points: Vec<mint::Point2<f32>>,
+    patrol_points: Vec<Waypoint>,
     current_patrol: usize,
+    route_mode: RouteMode,
+    patrol_step: i32, // 1 ili -1, u kom smeru ide kroz rutu (bitno za PingPong)
+    wait_ticks: i32, // koliko jos stoji na tacki rute
+    wait_facing: Option<Vector2<f32>>,
     vision_handle: CollisionObjectHandle,
-    triangle: [mint::Point2<f32>; 3],
+    pub vision: Vec<mint::Point2<f32>>, // vidno polje odseceno zidovima, prva tacka su oci strazara
+    pub state: GuardState,
+    pub detection: f32, // merac od 0 do 1, kad se napuni strazar krece u poteru
+    last_seen: mint::Point2<f32>, // gde je igrac poslednji put vidjen (centar oblika igraca)
+    pub path: Vec<TilePos>, // polja kroz koja strazar jos treba da prodje, za debug prikaz
+    path_goal: Option<TilePos>, // za koje ciljno polje je path izracunat
+    pub gave_up: i32, // koliko puta je strazar odustao od igraca i vratio se na rutu
 }
 
 impl Guard {
-    pub fn new(ctx: &mut Context, coor_1: mint::Point2<f32>, coor_2: mint::Point2<f32>, patrol_point_count: i32, handle: CollisionObjectHandle) -> Self {
-        // konstruktoru saljemo tacke koje oznacavaju koordinate sobe
-        let mut patrol: Vec<mint::Point2<f32>> = Vec::new();
-        let mut rng = thread_rng();
+    pub fn random_route(coor_1: mint::Point2<f32>, coor_2: mint::Point2<f32>, patrol_point_count: i32, rng: &mut StdRng) -> Vec<Waypoint> {
+        // saljemo tacke koje oznacavaju koordinate sobe, za sobe bez zadate rute
+        let mut patrol: Vec<Waypoint> = Vec::new();
         for _i in 0..patrol_point_count {
-            patrol.push(mint::Point2 { x: rng.gen_range(coor_1.x, coor_2.x),
-            y: rng.gen_range(coor_1.y, coor_2.y) });
+            patrol.push(Waypoint {
+                pos: mint::Point2 { x: rng.gen_range(coor_1.x, coor_2.x),
+                y: rng.gen_range(coor_1.y, coor_2.y) },
+                wait: 0,
+                facing: None,
+            });
         }
+        patrol
+    }
+
+    pub fn new(patrol: Vec<Waypoint>, mode: RouteMode, handle: CollisionObjectHandle) -> Self {
+        // ruta mora da ima bar jednu tacku, to proverava Map::load
         Guard {
-            pos: mint:: Point2 {x: patrol[0].x , y: patrol[0].y },
+            pos: mint:: Point2 {x: patrol[0].pos.x , y: patrol[0].pos.y },
+            prev_pos: patrol[0].pos,
             direction: Vector2::new(0.0, 1.0),
-            final_direction: Vector2::new(0.0, 0.0),
-            run_left: Animation::new(ctx, "/images/guard_runleft.png"),
-            run_right: Animation::new(ctx, "/images/guard_runright.png"),
-            run_down: Animation::new(ctx, "/images/guard_rundown.png"),
-            run_up: Animation::new(ctx, "/images/guard_runup.png"),
+            frames: Frames::new(7.0),
             animation_state: Direction::Down,
             spd: 3.3,
             turn_spd: 8.0,
-            rotation: Rotation2::new(0.0),
             total_rotation: 0.0,
-            next_point: mint:: Point2 {x: patrol[0].x , y: patrol[0].y },
-            patrol_points: patrol.clone(),
+            next_point: mint:: Point2 {x: patrol[0].pos.x , y: patrol[0].pos.y },
+            last_seen: patrol[0].pos,
+            patrol_points: patrol,
             current_patrol: 0,
+            route_mode: mode,
+            patrol_step: 1,
+            wait_ticks: 0,
+            wait_facing: None,
             vision_handle: handle,
-            triangle: [patrol[0], patrol[0], patrol[0]],
+            vision: Vec::new(),
+            state: GuardState::Patrol,
+            detection: 0.0,
+            path: Vec::new(),
+            path_goal: None,
+            gave_up: 0,
         }
     }
     fn direction_maker(&self, point_1: mint::Point2<f32>, point_2: mint::Point2<f32>) -> Vector2<f32> {
@@ -61,11 +143,22 @@ impl Guard {
         let dist_y = point_2.y - point_1.y;
         Vector2::new(dist_x, dist_y).normalize()
     }
-    fn next_rand_coor (&mut self) {
-        self.current_patrol = (self.current_patrol +1) % self.patrol_points.len();
-        self.next_point = self.patrol_points[self.current_patrol];
+    fn next_patrol_point (&mut self) {
+        // prvo stoji na tacki do koje je stigao, pa tek onda krece ka sledecoj
+        let arrived_at = &self.patrol_points[self.current_patrol];
+        self.wait_ticks = arrived_at.wait;
+        self.wait_facing = arrived_at.facing;
+        let count = self.patrol_points.len() as i32;
+        if self.route_mode == RouteMode::PingPong && count > 1 {
+            let next = self.current_patrol as i32 + self.patrol_step;
+            if next < 0 || next >= count {
+                self.patrol_step = -self.patrol_step; // stigao je do kraja rute, vraca se nazad
+            }
+        }
+        self.current_patrol = ((self.current_patrol as i32 + self.patrol_step + count) % count) as usize;
+        self.next_point = self.patrol_points[self.current_patrol].pos;
     }
-    fn pos_from_move(&self) -> mint::Point2<f32> {  // kopirana funkcija iz Player
+    fn pos_from_move(&self, spd: f32) -> mint::Point2<f32> {  // kopirana funkcija iz Player
         // ova f-ja se poziva pri svakom apdejtu
         // na osnovu trenutne pozicije i pravca kretanja
         // vraca "sledecu" poziciju igraca
@@ -74,58 +167,334 @@ impl Guard {
             norm_dir = self.direction.normalize();
         }
 
-        mint::Point2 { x: self.pos.x + norm_dir.x * self.spd, y: self.pos.y + norm_dir.y * self.spd }
-    }
-    pub fn update(&mut self, world: &mut CollisionWorld<f32, ()>, player_handle: CollisionObjectHandle, map_vel: Vector2<f32>) -> bool {
-        let mut caught_player: bool = false;
-        self.pos.x += map_vel.x;
-        self.pos.y += map_vel.y;
-        for i in 0..self.patrol_points.len() {
-            self.patrol_points[i].x += map_vel.x;
-            self.patrol_points[i].y += map_vel.y;
-        }
-        self.next_point.x += map_vel.x;
-        self.next_point.y += map_vel.y;
-        if (self.pos.x.abs() - self.next_point.x.abs()).abs() > (self.spd + 0.2) &&
-            (self.pos.y.abs() - self.next_point.y.abs()).abs() > (self.spd + 0.2) {
-            if self.final_direction.relative_eq(&self.direction, 0.00006, 0.0006) == false {
-                let iso = Isometry2::new(Vector2::new(0.0, 0.0), self.rotation.angle());
-                self.total_rotation += self.rotation.angle();
-                self.direction = iso.transform_vector(&self.direction);
-            } else {
-                self.pos = self.pos_from_move();
+        mint::Point2 { x: self.pos.x + norm_dir.x * spd, y: self.pos.y + norm_dir.y * spd }
+    }
+    fn eye_point(&self) -> mint::Point2<f32> {
+        // odavde strazar gleda, isto mesto gde stoji trougao vidnog polja
+        mint::Point2 { x: self.pos.x+16.0, y: self.pos.y+13.0 }
+    }
+    fn reached(&self, point: mint::Point2<f32>, spd: f32) -> bool {
+        let dist_x = point.x - self.pos.x;
+        let dist_y = point.y - self.pos.y;
+        (dist_x*dist_x + dist_y*dist_y).sqrt() <= spd + 0.2
+    }
+    fn turn_towards(&mut self, point: mint::Point2<f32>) -> bool {
+        // okrece strazara ka tacki najvise za PI/turn_spd po ticku,
+        // vraca true kad je strazar okrenut ka tacki
+        if self.reached(point, 0.0) {
+            return true
+        }
+        let wanted = self.direction_maker(self.pos, point);
+        let angle = Rotation2::rotation_between(&self.direction, &wanted).angle();
+        let max_turn = PI/self.turn_spd;
+        let turn = if angle.abs() <= max_turn { angle } else { max_turn*angle.signum() };
+        self.direction = Isometry2::new(Vector2::new(0.0, 0.0), turn).transform_vector(&self.direction);
+        self.total_rotation += turn;
+        angle.abs() <= max_turn
+    }
+    fn walk_towards(&mut self, point: mint::Point2<f32>, spd: f32) -> bool {
+        // prvo se okrene, pa tek onda hoda; vraca true kad stigne do tacke
+        if self.reached(point, spd) {
+            return true
+        }
+        if self.turn_towards(point) {
+            self.pos = self.pos_from_move(spd);
+        }
+        false
+    }
+    fn walk_path(&mut self, point: mint::Point2<f32>, spd: f32, grid: &Grid, map_start: mint::Point2<f32>) -> bool {
+        // kao walk_towards, ali ide A* putanjom kroz polja mape umesto pravo kroz zidove.
+        // Polozaji su gornji levi ugao slike strazara, pa gledamo polje u kom je centar
+        let center = |p: mint::Point2<f32>| mint::Point2 { x: p.x+16.0, y: p.y+16.0 };
+        let start_tile = grid.tile_of(center(self.pos), map_start);
+        let point_tile = grid.tile_of(center(point), map_start);
+        let goal_tile = point_tile.and_then(|t| grid.nearest_walkable(t));
+        let (start_tile, goal_tile) = match (start_tile, goal_tile) {
+            (Some(start), Some(goal)) => (start, goal),
+            _ => return self.walk_towards(point, spd), // van mape nema mreze, idemo pravo
+        };
+        if self.path_goal != Some(goal_tile) {
+            self.path_goal = Some(goal_tile);
+            self.path = match grid.find_path(start_tile, goal_tile) {
+                Some(path) => path,
+                None => {
+                    // do cilja ne moze da se stigne, ponasamo se kao da je stigao
+                    self.path_goal = None;
+                    return true
+                },
+            };
+        }
+        if self.path.is_empty() {
+            // u ciljnom smo polju; ako tacka nije u zidu idemo bas do nje
+            let last_point = if point_tile == Some(goal_tile) { point } else { grid.point_of(goal_tile, map_start) };
+            let arrived = self.walk_towards(last_point, spd);
+            if arrived {
+                self.path_goal = None;
             }
-        } else {
-            self.next_rand_coor();
-            self.final_direction = self.direction_maker(self.pos, self.next_point);
-            self.rotation = Rotation2::scaled_rotation_between(&self.direction, &self.final_direction, 1.0/self.turn_spd);
+            return arrived
         }
-        world.set_position(self.vision_handle, Isometry2::new(Vector2::new(self.pos.x+16.0, self.pos.y+13.0), self.total_rotation));
+        if self.walk_towards(grid.point_of(self.path[0], map_start), spd) {
+            self.path.remove(0);
+        }
+        false
+    }
+    pub fn route_len(&self) -> usize {
+        self.patrol_points.len()
+    }
+    pub fn snapshot(&self) -> GuardSnapshot {
+        GuardSnapshot {
+            pos: self.pos,
+            patrol: self.current_patrol,
+            patrol_step: self.patrol_step,
+            rotation: self.total_rotation,
+            gave_up: self.gave_up,
+        }
+    }
+    pub fn restore(&mut self, snapshot: &GuardSnapshot) {
+        // strazar se vraca na rutu A* putanjom, pa ne mora da se pamti sta je tacno radio
+        self.pos = snapshot.pos;
+        self.prev_pos = snapshot.pos;
+        // Simulation::restore i SaveGame::load su vec proverili tacku i smer
+        self.current_patrol = snapshot.patrol;
+        self.patrol_step = snapshot.patrol_step;
+        self.total_rotation = snapshot.rotation;
+        self.direction = Isometry2::new(Vector2::new(0.0, 0.0), snapshot.rotation).transform_vector(&Vector2::new(0.0, 1.0));
+        self.gave_up = snapshot.gave_up;
+        self.next_point = self.patrol_points[self.current_patrol].pos;
+        self.state = GuardState::Return;
+        self.detection = 0.0;
+        self.wait_ticks = 0;
+        self.path.clear();
+        self.path_goal = None;
+    }
+    pub fn alertness(&self) -> Alertness {
+        match self.state {
+            // strazar koji proverava zvuk igraca nije video, pa se to ne racuna kao da je igrac primecen
+            GuardState::Patrol | GuardState::Return | GuardState::Investigate(_) => Alertness::Calm,
+            GuardState::Suspicious => Alertness::Suspicious,
+            GuardState::Search(_) => Alertness::Searching,
+            GuardState::Alert => Alertness::Alert,
+        }
+    }
+    fn wall_distance(&self, world: &CollisionWorld<f32, ()>, map_handle: CollisionObjectHandle, ray: &Ray<f32>, range: f32) -> f32 {
+        // isto kao vidljivost u Player::update - zrak se sece sa Compound oblikom mape.
+        // solid je false da strazar koji je zagazio u zid gleda od ivice tog zida, a ne nigde
+        let map_object = world.collision_object(map_handle).unwrap();
+        let map_shape: &Compound<f32> = map_object.shape().as_shape().unwrap();
+        match map_shape.toi_with_ray(map_object.position(), ray, false) {
+            Some(toi) if toi < range => toi,
+            _ => range,
+        }
+    }
 
-/*
-        let triangle_shape: &ConvexPolygon<f32> = world.collision_object(self.vision_handle).unwrap().shape().as_shape().unwrap();
-        let triangle_points: &[Point2<f32>] = triangle_shape.points();
-        self.triangle = [mint::Point2 { x: triangle_points[0].x, y: triangle_points[0].y },
-                         mint::Point2 { x: triangle_points[1].x, y: triangle_points[1].y },
-                         mint::Point2 { x: triangle_points[2].x, y: triangle_points[2].y }];
-*/
-        let vec1 = Isometry2::new(Vector2::new(0.0,0.0), PI/6.0).transform_vector(&self.direction)*64.0;
-        let vec2 = Isometry2::new(Vector2::new(0.0,0.0), -PI/6.0).transform_vector(&self.direction)*64.0;
-        let origin_point = mint::Point2 {x: self.pos.x+16.0, y: self.pos.y + 13.0};
-        self.triangle = [origin_point, 
-                          mint::Point2 {x: origin_point.x + vec1.x, y: origin_point.y + vec1.y}, 
-                          mint::Point2{x: origin_point.x + vec2.x, y: origin_point.y + vec2.y}];
-
-        match world.contact_pair(self.vision_handle, player_handle, true) {
-            // contact_pair vraca uredjenu cetvorku koja opisuje da li se desio sudar
-            None => (),
-            _ => {
-                caught_player = true;
-                ()
-            },
+    fn update_vision(&mut self, world: &CollisionWorld<f32, ()>, map_handle: CollisionObjectHandle) {
+        // umesto jednog trougla pravimo lepezu zrakova i svaki skracujemo do prvog zida
+        let eye = self.eye_point();
+        let origin_point = Point2::new(eye.x, eye.y);
+        self.vision.clear();
+        self.vision.push(mint::Point2 { x: origin_point.x, y: origin_point.y });
+        for i in 0..VISION_RAYS+1 {
+            let angle = -PI/6.0 + (PI/3.0)*(i as f32)/(VISION_RAYS as f32);
+            let ray_dir = Isometry2::new(Vector2::new(0.0, 0.0), angle).transform_vector(&self.direction);
+            let ray = Ray::new(origin_point, ray_dir);
+            let end_point = ray.point_at(self.wall_distance(world, map_handle, &ray, VISION_RANGE));
+            self.vision.push(mint::Point2 { x: end_point.x, y: end_point.y });
+        }
+    }
+
+    fn sees_point(&self, world: &CollisionWorld<f32, ()>, map_handle: CollisionObjectHandle, point: Point2<f32>) -> bool {
+        // da li izmedju ociju strazara i tacke postoji zid
+        let eye = self.eye_point();
+        let origin_point = Point2::new(eye.x, eye.y);
+        let to_point = point - origin_point;
+        let distance = to_point.norm();
+        if distance == 0.0 {
+            return true
+        }
+        let ray = Ray::new(origin_point, to_point/distance);
+        self.wall_distance(world, map_handle, &ray, VISION_RANGE) >= distance.min(VISION_RANGE)
+    }
+
+    fn sees_player(&self, world: &CollisionWorld<f32, ()>, map_handle: CollisionObjectHandle, player_handle: CollisionObjectHandle) -> bool {
+        // igrac je vidljiv ako mu se vidi centar ili bilo koji ugao (oblik igraca je 24x16)
+        let player_center = world.collision_object(player_handle).unwrap().position().translation.vector;
+        let offsets = [(0.0, 0.0), (-12.0, -8.0), (12.0, -8.0), (-12.0, 8.0), (12.0, 8.0)];
+        offsets.iter().any(|&(dx, dy)| self.sees_point(world, map_handle, Point2::new(player_center.x+dx, player_center.y+dy)))
+    }
+
+    fn hears(&self, world: &CollisionWorld<f32, ()>, map_handle: CollisionObjectHandle, noise: &Noise) -> bool {
+        // zvuk se cuje iz svih pravaca, pa i iza leda, ali ne kroz zid
+        let eye = self.eye_point();
+        let distance = noise.distance(eye);
+        if distance > noise.radius {
+            return false
+        }
+        if distance == 0.0 {
+            return true
         }
+        let origin_point = Point2::new(eye.x, eye.y);
+        let ray = Ray::new(origin_point, (Point2::new(noise.pos.x, noise.pos.y) - origin_point)/distance);
+        self.wall_distance(world, map_handle, &ray, distance) >= distance
+    }
 
+    fn search_point(&self, rng: &mut StdRng) -> mint::Point2<f32> {
+        // nasumicna tacka oko mesta gde je igrac poslednji put vidjen
+        mint::Point2 { x: self.last_seen.x - 16.0 + rng.gen_range(-SEARCH_RADIUS, SEARCH_RADIUS),
+                       y: self.last_seen.y - 13.0 + rng.gen_range(-SEARCH_RADIUS, SEARCH_RADIUS) }
+    }
 
+    pub fn update(&mut self, world: &mut CollisionWorld<f32, ()>, player_handle: CollisionObjectHandle, map_handle: CollisionObjectHandle, grid: &Grid, map_start: mint::Point2<f32>, noises: &[Noise], rng: &mut StdRng) -> Alertness {
+        self.prev_pos = self.pos;
+
+        // prvo gledamo, pa tek onda odlucujemo sta strazar radi u ovom ticku
+        self.update_vision(world, map_handle);
+        let player_center = world.collision_object(player_handle).unwrap().position().translation.vector;
+        let player_point = mint::Point2 { x: player_center.x, y: player_center.y };
+        let seen = match world.contact_pair(self.vision_handle, player_handle, true) {
+            // contact_pair vraca uredjenu cetvorku koja opisuje da li se desio sudar.
+            // Trougao ne zna za zidove, pa jos proveravamo da li zid zaklanja igraca
+            Some(_) => self.sees_player(world, map_handle, player_handle),
+            None => false,
+        };
+        let eye = self.eye_point();
+        let player_distance = ((player_point.x - eye.x).powi(2) + (player_point.y - eye.y).powi(2)).sqrt();
+        // od svih zvukova koje cuje, strazar ide ka najblizem. Koraci su jedini zvuk koji
+        // odaje bas igraca, pa samo oni pune merac
+        let mut heard: Option<Noise> = None;
+        let mut footsteps: Option<Noise> = None;
+        for noise in noises.iter().filter(|n| self.hears(world, map_handle, n)) {
+            if heard.map_or(true, |h| noise.distance(eye) < h.distance(eye)) {
+                heard = Some(*noise);
+            }
+            if noise.kind == NoiseKind::Footsteps {
+                footsteps = Some(*noise);
+            }
+        }
+        // mesto zvuka kao pozicija strazara (gornji levi ugao slike), kao kod last_seen
+        let heard = heard.map(|n| mint::Point2 { x: n.pos.x - 16.0, y: n.pos.y - 13.0 });
+
+        if seen {
+            // sto je igrac blizi, merac se brze puni (do tri puta brze kad je tik uz strazara)
+            let closeness = 1.0 - (player_distance/VISION_RANGE).min(1.0);
+            self.detection = (self.detection + DETECTION_FILL*(1.0 + 2.0*closeness)).min(1.0);
+            self.last_seen = player_point;
+        } else if let Some(steps) = footsteps {
+            // strazar ne zna ko hoda, ali se merac polako puni
+            self.detection = (self.detection + HEARING_FILL).min(1.0);
+            self.last_seen = steps.pos;
+        } else {
+            self.detection = (self.detection - DETECTION_DECAY).max(0.0);
+        }
+
+        let mut caught_player = false;
+        let old_state = self.state;
+        self.state = match self.state {
+            GuardState::Patrol => {
+                if seen {
+                    GuardState::Suspicious
+                } else if let Some(spot) = heard {
+                    self.next_point = spot;
+                    GuardState::Investigate(INVESTIGATE_TICKS)
+                } else {
+                    if self.wait_ticks > 0 {
+                        self.wait_ticks -= 1;
+                        if let Some(facing) = self.wait_facing {
+                            let look_at = mint::Point2 { x: self.pos.x + facing.x*32.0, y: self.pos.y + facing.y*32.0 };
+                            self.turn_towards(look_at);
+                        }
+                    } else if self.walk_path(self.next_point, self.spd, grid, map_start) {
+                        self.next_patrol_point();
+                    }
+                    GuardState::Patrol
+                }
+            },
+            GuardState::Suspicious => {
+                let last_seen = mint::Point2 { x: self.last_seen.x - 16.0, y: self.last_seen.y - 13.0 };
+                self.turn_towards(last_seen);
+                if self.detection >= 1.0 {
+                    GuardState::Alert
+                } else if self.detection <= 0.0 {
+                    GuardState::Return
+                } else {
+                    GuardState::Suspicious
+                }
+            },
+            GuardState::Alert => {
+                if seen && player_distance <= CATCH_DISTANCE {
+                    caught_player = true;
+                }
+                let last_seen = mint::Point2 { x: self.last_seen.x - 16.0, y: self.last_seen.y - 13.0 };
+                let arrived = self.walk_path(last_seen, CHASE_SPD, grid, map_start);
+                if seen {
+                    GuardState::Alert
+                } else if arrived {
+                    // izgubio ga je iz vida, pretrazuje okolinu
+                    self.next_point = self.search_point(rng);
+                    GuardState::Search(SEARCH_TICKS)
+                } else {
+                    GuardState::Alert // jos trci ka mestu gde ga je poslednji put video
+                }
+            },
+            GuardState::Search(ticks_left) => {
+                if seen {
+                    GuardState::Alert
+                } else if ticks_left <= 0 && heard.is_none() {
+                    self.next_point = self.patrol_points[self.current_patrol].pos;
+                    GuardState::Return
+                } else {
+                    if let Some(spot) = heard {
+                        // cuo je nesto dok trazi, ide tamo da proveri i trazi ispocetka
+                        self.next_point = spot;
+                    }
+                    if self.walk_path(self.next_point, self.spd, grid, map_start) {
+                        self.next_point = self.search_point(rng);
+                    }
+                    GuardState::Search(if heard.is_some() { SEARCH_TICKS } else { ticks_left - 1 })
+                }
+            },
+            GuardState::Investigate(ticks_left) => {
+                if seen {
+                    GuardState::Suspicious
+                } else if self.detection >= 1.0 {
+                    // dugo je slusao korake, sigurno je da neko tu hoda
+                    GuardState::Alert
+                } else if let Some(spot) = heard {
+                    // novi zvuk, proverava njega
+                    self.next_point = spot;
+                    GuardState::Investigate(INVESTIGATE_TICKS)
+                } else if ticks_left <= 0 {
+                    self.next_point = self.patrol_points[self.current_patrol].pos;
+                    GuardState::Return
+                } else {
+                    // kad stigne, stoji na mestu i gleda okolo dok ne istekne vreme
+                    self.walk_path(self.next_point, self.spd, grid, map_start);
+                    GuardState::Investigate(ticks_left - 1)
+                }
+            },
+            GuardState::Return => {
+                if seen {
+                    GuardState::Suspicious
+                } else if let Some(spot) = heard {
+                    self.next_point = spot;
+                    GuardState::Investigate(INVESTIGATE_TICKS)
+                } else if self.walk_path(self.patrol_points[self.current_patrol].pos, self.spd, grid, map_start) {
+                    self.next_point = self.patrol_points[self.current_patrol].pos;
+                    GuardState::Patrol
+                } else {
+                    GuardState::Return
+                }
+            },
+        };
+        if self.state == GuardState::Return && old_state != GuardState::Return && !matches!(old_state, GuardState::Investigate(_)) {
+            // posle provere zvuka se ne racuna, tad nije ni trazio igraca
+            self.gave_up += 1;
+        }
+        if std::mem::discriminant(&old_state) != std::mem::discriminant(&self.state) {
+            // novo stanje ima nov cilj, stara putanja vise ne vazi
+            self.path.clear();
+            self.path_goal = None;
+        }
+        world.set_position(self.vision_handle, Isometry2::new(Vector2::new(self.pos.x+16.0, self.pos.y+13.0), self.total_rotation));
 
         if self.direction.x > 0.0  && ((self.direction.x).abs() - (self.direction.y).abs()) > 0.0 {
             self.animation_state = Direction::Right;
@@ -139,31 +508,14 @@ impl Guard {
         else if self.direction.y < 0.0  && (self.direction.x.abs() - self.direction.y.abs()) < 0.0 {
             self.animation_state = Direction::Up;
         }
-        match self.animation_state {
-            Direction::Right => self.run_right.next_frame(),
-            Direction::Left => self.run_left.next_frame(),
-            Direction::Up => self.run_up.next_frame(),
-            Direction::Down => self.run_down.next_frame(),
-            Direction::Null => (),
+        if self.animation_state != Direction::Null {
+            self.frames.next_frame();
         }
 
-        caught_player
-    }
-    pub fn draw_vision(&self, ctx: &mut Context) -> GameResult<()> {
-        let vision = graphics::Mesh::from_triangles(ctx, &self.triangle, [1.0, 0.0, 0.0, 0.5].into())?;
-        graphics::draw(ctx, &vision, graphics::DrawParam::new())?;
-        Ok(())
-
-    }
-
-    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
-        match self.animation_state {
-            Direction::Right => self.run_right.draw(ctx, self.pos)?,
-            Direction::Left => self.run_left.draw(ctx, self.pos)?,
-            Direction::Up => self.run_up.draw(ctx, self.pos)?,
-            Direction::Down => self.run_down.draw(ctx, self.pos)?,
-            Direction::Null => (),
+        if caught_player {
+            Alertness::Caught
+        } else {
+            self.alertness()
         }
-        Ok(())
     }
 }
@@ -0,0 +1,193 @@
+use std::collections::VecDeque;
+use ggez::*;
+use ggez::audio::SoundSource;
+
+use crate::campaign::Campaign;
+use crate::high_scores::{HighScores, CAMPAIGN_TABLE, TABLE_SIZE};
+use crate::bindings::Action;
+use crate::scene::{Scene, Shared, Transition};
+use crate::screen;
+use crate::ui::{self, MenuInput};
+
+const PANEL_OFFSET: f32 = 390.0; // isto kao u LevelSelect
+const ROW_HEIGHT: f32 = 36.0;
+
+// Panel sa tabelama najboljih rezultata, jedna tabela za kampanju i po jedna za svaki nivo
+pub struct HighScorePanel {
+    offset: f32,
+    stone_pos: mint::Point2<f32>,
+    tables: Vec<(graphics::Text, Vec<graphics::Text>)>, // naslov tabele i redovi
+    current: usize, // koja tabela se prikazuje
+    title_pos: mint::Point2<f32>,
+    rows_pos: mint::Point2<f32>,
+    next: graphics::Text,
+    next_pos: mint::Point2<f32>,
+    back: graphics::Text,
+    back_pos: mint::Point2<f32>,
+    stone: graphics::Image,
+    stone_sound: audio::Source,
+    was_pressed: bool, // da jedan klik na "Next" ne bi preskocio vise tabela
+    closing: bool,
+    focus: Option<usize>, // 0 je Next, 1 je Back
+    menu_input: VecDeque<MenuInput>, // sve sto je stiglo od proslog updatea, obradjuje se redom
+    last_mouse: mint::Point2<f32>,
+}
+
+impl HighScorePanel {
+    pub fn new(ctx: &mut Context, campaign: &Campaign, high_scores: &HighScores) -> GameResult<Self> {
+        let font_celtknot = graphics::Font::new(ctx, "/fonts/Celtknot.ttf")?;
+        let mut names: Vec<(String, String)> = vec![(CAMPAIGN_TABLE.to_string(), "Campaign".to_string())];
+        for file in campaign.level_files(ctx)? {
+            let name = campaign.name_of(&file);
+            names.push((file, name));
+        }
+        let mut tables: Vec<(graphics::Text, Vec<graphics::Text>)> = Vec::new();
+        for (key, name) in names {
+            let mut title = graphics::Text::new(name);
+            title.set_font(font_celtknot, graphics::Scale::uniform(40.0));
+            let mut rows: Vec<graphics::Text> = Vec::new();
+            for (i, score) in high_scores.table(&key).iter().enumerate() {
+                let mut row = graphics::Text::new(format!("{}. {}   {} gold   {}   {}", i+1, score.initials, score.gold, score.time(), score.date));
+                row.set_font(font_celtknot, graphics::Scale::uniform(24.0));
+                rows.push(row);
+            }
+            if rows.is_empty() {
+                let mut row = graphics::Text::new("No records yet");
+                row.set_font(font_celtknot, graphics::Scale::uniform(24.0));
+                rows.push(row);
+            }
+            tables.push((title, rows));
+        }
+        let mut next = graphics::Text::new("Next");
+        next.set_font(font_celtknot, graphics::Scale::uniform(30.0));
+        let mut back = graphics::Text::new("Back");
+        back.set_font(font_celtknot, graphics::Scale::uniform(30.0));
+        let mut stone_sound = audio::Source::new(ctx, "/sounds/stone_short.mp3")?;
+        stone_sound.play_detached()?;
+
+        Ok(HighScorePanel {
+            offset: PANEL_OFFSET,
+            stone_pos: mint::Point2 { x: 40.0, y: 40.0 },
+            tables: tables,
+            current: 0,
+            title_pos: mint::Point2 { x: 100.0, y: 80.0 },
+            rows_pos: mint::Point2 { x: 100.0, y: 150.0 },
+            next: next,
+            next_pos: mint::Point2 { x: 180.0, y: 400.0 },
+            back: back,
+            back_pos: mint::Point2 { x: 360.0, y: 400.0 },
+            stone: graphics::Image::new(ctx, "/images/user_interface.png")?,
+            stone_sound: stone_sound,
+            was_pressed: true,
+            closing: false,
+            focus: None,
+            menu_input: VecDeque::new(),
+            last_mouse: screen::mouse_position(ctx),
+        })
+    }
+
+    fn shifted(&self, pos: mint::Point2<f32>) -> mint::Point2<f32> {
+        mint::Point2 { x: pos.x, y: pos.y + self.offset }
+    }
+
+    fn turn_page(&mut self, step: usize) {
+        // step je 1 za sledecu tabelu, a tables.len()-1 za prethodnu
+        self.current = (self.current + step) % self.tables.len();
+    }
+
+    fn close(&mut self) {
+        if !self.closing {
+            self.closing = true;
+            self.stone_sound.play_detached().unwrap();
+        }
+    }
+
+    fn activate(&mut self, item: usize) {
+        if item == 0 {
+            self.turn_page(1);
+        } else {
+            self.close();
+        }
+    }
+}
+
+impl Scene for HighScorePanel {
+    fn update(&mut self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<Transition> {
+        // kad se kamen vrati dole, panel se sklanja i vidi se meni
+        if self.closing {
+            self.offset += 5.0;
+            if self.offset >= PANEL_OFFSET {
+                return Ok(Transition::Pop)
+            }
+            return Ok(Transition::Stay)
+        }
+        if self.offset > 0.0 {
+            self.offset -= 5.0;
+            return Ok(Transition::Stay)
+        }
+        let pressed = input::mouse::button_pressed(ctx, input::mouse::MouseButton::Left);
+        let clicked = pressed && !self.was_pressed;
+        self.was_pressed = pressed;
+
+        let hovered = if ui::hovered(ctx, &self.next, self.next_pos) {
+            Some(0)
+        } else if ui::hovered(ctx, &self.back, self.back_pos) {
+            Some(1)
+        } else {
+            None
+        };
+        let mouse_pos = screen::mouse_position(ctx);
+        if mouse_pos != self.last_mouse {
+            // mis se pomerio, fokus prati mis
+            self.last_mouse = mouse_pos;
+            self.focus = hovered;
+        }
+        while let Some(menu_input) = self.menu_input.pop_front() {
+            // gore i dole biraju Next ili Back, a levo i desno listaju tabele
+            let tables = self.tables.len();
+            match menu_input {
+                MenuInput::Up | MenuInput::Down => self.focus = Some(if self.focus == Some(0) { 1 } else { 0 }),
+                MenuInput::Left => self.turn_page(tables - 1),
+                MenuInput::Right => self.turn_page(1),
+                MenuInput::Confirm => if let Some(item) = self.focus {
+                    self.activate(item);
+                },
+                MenuInput::Back => self.close(),
+            }
+        }
+        if clicked {
+            if let Some(item) = hovered {
+                self.activate(item);
+            }
+        }
+        let on = self.focus == Some(0);
+        ui::highlight(&mut self.next, on);
+        let on = self.focus == Some(1);
+        ui::highlight(&mut self.back, on);
+        Ok(Transition::Stay)
+    }
+
+    fn draw(&self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<()> {
+        graphics::draw(ctx, &self.stone, graphics::DrawParam::new().dest(self.shifted(self.stone_pos)))?;
+        let (title, rows) = &self.tables[self.current];
+        graphics::draw(ctx, title, graphics::DrawParam::new().dest(self.shifted(self.title_pos)))?;
+        for (i, row) in rows.iter().take(TABLE_SIZE).enumerate() {
+            let pos = mint::Point2 { x: self.rows_pos.x, y: self.rows_pos.y + i as f32*ROW_HEIGHT };
+            graphics::draw(ctx, row, graphics::DrawParam::new().dest(self.shifted(pos)))?;
+        }
+        graphics::draw(ctx, &self.next, graphics::DrawParam::new().dest(self.shifted(self.next_pos)))?;
+        graphics::draw(ctx, &self.back, graphics::DrawParam::new().dest(self.shifted(self.back_pos)))?;
+        Ok(())
+    }
+
+    fn action(&mut self, action: Action) {
+        // Esc ili Start vraca u glavni meni, kao Back
+        if action == Action::Pause {
+            self.close();
+        }
+    }
+
+    fn menu(&mut self, input: MenuInput) {
+        self.menu_input.push_back(input);
+    }
+}
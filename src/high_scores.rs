@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use ggez::{filesystem, Context, GameResult};
+
+use crate::sim::TICKS_PER_SECOND;
+
+pub const HIGH_SCORES_FILENAME: &str = "high_scores.txt";
+pub const CAMPAIGN_TABLE: &str = "campaign"; // tabela za celu kampanju, ostale tabele su po fajlu nivoa
+pub const TABLE_SIZE: usize = 5;
+
+#[derive(Debug, Clone)]
+pub struct HighScore {
+    pub initials: String,
+    pub gold: i32,
+    pub ticks: u32, // trajanje u tickovima simulacije
+    pub date: String, // GGGG-MM-DD
+}
+
+impl HighScore {
+    pub fn time(&self) -> String {
+        let seconds = (self.ticks as f32/TICKS_PER_SECOND) as u32;
+        format!("{:02}:{:02}", seconds/60, seconds%60)
+    }
+
+    fn better_than(&self, gold: i32, ticks: u32) -> bool {
+        // vise zlata je bolje, a za isto zlato brze
+        self.gold > gold || (self.gold == gold && self.ticks <= ticks)
+    }
+}
+
+pub fn today() -> String {
+    // datum iz UNIX vremena, bez dodatne biblioteke (algoritam "civil_from_days")
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()/86400).unwrap_or(0) as i64;
+    let z = days + 719468;
+    let era = z/146097;
+    let doe = z - era*146097;
+    let yoe = (doe - doe/1460 + doe/36524 - doe/146096)/365;
+    let doy = doe - (365*yoe + yoe/4 - yoe/100);
+    let mp = (5*doy + 2)/153;
+    let day = doy - (153*mp + 2)/5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era*400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Tabele najboljih rezultata, cuvaju se u ggez direktorijumu za korisnicke podatke.
+// Svaka linija fajla je "tabela inicijali zlato tickovi datum"
+#[derive(Debug, Clone)]
+pub struct HighScores {
+    tables: HashMap<String, Vec<HighScore>>,
+    path: PathBuf,
+}
+
+impl HighScores {
+    pub fn load(ctx: &Context) -> Self {
+        // kao i kod Progress, ostecene linije preskacemo umesto da igra ne moze da se pokrene
+        let path = filesystem::user_data_dir(ctx).join(HIGH_SCORES_FILENAME);
+        let mut high_scores = HighScores {
+            tables: HashMap::new(),
+            path: path,
+        };
+        if let Ok(text) = fs::read_to_string(&high_scores.path) {
+            for line in text.lines() {
+                let fields: Vec<&str> = line.split(' ').collect();
+                if fields.len() != 5 {
+                    continue;
+                }
+                if let (Ok(gold), Ok(ticks)) = (fields[2].parse::<i32>(), fields[3].parse::<u32>()) {
+                    high_scores.insert(fields[0], HighScore {
+                        initials: fields[1].to_string(),
+                        gold: gold,
+                        ticks: ticks,
+                        date: fields[4].to_string(),
+                    });
+                }
+            }
+        }
+        high_scores
+    }
+
+    pub fn table(&self, name: &str) -> &[HighScore] {
+        match self.tables.get(name) {
+            Some(t) => t,
+            None => &[],
+        }
+    }
+
+    pub fn qualifies(&self, name: &str, gold: i32, ticks: u32) -> bool {
+        // da li bi rezultat usao u tabelu
+        let table = self.table(name);
+        gold > 0 && (table.len() < TABLE_SIZE || table.iter().any(|s| !s.better_than(gold, ticks)))
+    }
+
+    pub fn insert(&mut self, name: &str, score: HighScore) {
+        let table = self.tables.entry(name.to_string()).or_insert_with(Vec::new);
+        let index = table.iter().position(|s| !s.better_than(score.gold, score.ticks)).unwrap_or(table.len());
+        table.insert(index, score);
+        table.truncate(TABLE_SIZE);
+    }
+
+    pub fn save(&self) -> GameResult<()> {
+        let mut names: Vec<&String> = self.tables.keys().collect();
+        names.sort();
+        let mut text = String::new();
+        for name in names {
+            for score in self.tables[name].iter() {
+                text.push_str(&format!("{} {} {} {} {}\n", name, score.initials, score.gold, score.ticks, score.date));
+            }
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, text)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty() -> HighScores {
+        HighScores { tables: HashMap::new(), path: PathBuf::new() }
+    }
+
+    fn score(initials: &str, gold: i32, ticks: u32) -> HighScore {
+        HighScore { initials: initials.to_string(), gold: gold, ticks: ticks, date: "2020-01-01".to_string() }
+    }
+
+    fn initials(high_scores: &HighScores) -> Vec<&str> {
+        high_scores.table("lvl").iter().map(|s| s.initials.as_str()).collect()
+    }
+
+    #[test]
+    fn no_gold_never_qualifies() {
+        let high_scores = empty();
+        assert!(!high_scores.qualifies("lvl", 0, 10));
+        assert!(high_scores.qualifies("lvl", 1, 10));
+    }
+
+    #[test]
+    fn sorted_by_gold_then_time() {
+        let mut high_scores = empty();
+        high_scores.insert("lvl", score("AAA", 10, 500));
+        high_scores.insert("lvl", score("BBB", 30, 900));
+        high_scores.insert("lvl", score("CCC", 10, 300));
+        high_scores.insert("lvl", score("DDD", 10, 500)); // isti rezultat ide posle starijeg
+        assert_eq!(initials(&high_scores), vec!["BBB", "CCC", "AAA", "DDD"]);
+        assert!(high_scores.table("other").is_empty());
+    }
+
+    #[test]
+    fn full_table_keeps_best_five() {
+        let mut high_scores = empty();
+        for gold in 1..=TABLE_SIZE as i32 {
+            assert!(high_scores.qualifies("lvl", gold*10, 100));
+            high_scores.insert("lvl", score(&format!("P{}", gold), gold*10, 100));
+        }
+        // najslabiji je 10 zlata za 100 tickova
+        assert!(!high_scores.qualifies("lvl", 5, 1));
+        assert!(!high_scores.qualifies("lvl", 10, 100));
+        assert!(high_scores.qualifies("lvl", 10, 99));
+        high_scores.insert("lvl", score("NEW", 20, 50));
+        assert_eq!(initials(&high_scores), vec!["P5", "P4", "P3", "NEW", "P2"]);
+    }
+}
@@ -0,0 +1,66 @@
+use std::fmt;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use ggez::{filesystem, Context, GameError, GameResult};
+
+// Greska pri ucitavanju nivoa - pamtimo fajl, liniju i kolonu (obe krecu od 1)
+// da bi dizajner nivoa odmah znao gde je pogresio
+#[derive(Debug, Clone)]
+pub struct LevelError {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub description: String,
+}
+
+impl LevelError {
+    pub fn new(file: &str, line: usize, column: usize, description: String) -> Self {
+        LevelError {
+            file: file.to_string(),
+            line: line,
+            column: column,
+            description: description,
+        }
+    }
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}: {}", self.file, self.line, self.column, self.description)
+    }
+}
+
+impl From<LevelError> for GameError {
+    // da bi mogli da koristimo ? u funkcijama koje vracaju GameResult
+    fn from(e: LevelError) -> GameError {
+        GameError::ResourceLoadError(e.to_string())
+    }
+}
+
+pub fn split_fields(text: &str, sep: char) -> Vec<(usize, &str)> {
+    // deli liniju po separatoru i uz svako polje vraca kolonu (od 1) na kojoj polje pocinje
+    let mut fields: Vec<(usize, &str)> = Vec::new();
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        if c == sep {
+            fields.push((text[..start].chars().count() + 1, &text[start..i]));
+            start = i + c.len_utf8();
+        }
+    }
+    fields.push((text[..start].chars().count() + 1, &text[start..]));
+    fields
+}
+
+pub fn parse_field<T: FromStr>(file: &str, line: usize, column: usize, field: &str, what: &str) -> Result<T, LevelError> {
+    field.parse().map_err(|_| LevelError::new(file, line, column,
+                                              format!("{}: expected a number, found '{}'", what, field)))
+}
+
+pub fn read_level_file<P: AsRef<Path>>(ctx: &mut Context, level_filename: P) -> GameResult<String> {
+    // jedino mesto gde nam za nivo treba ggez kontekst, sam tekst se dalje parsira u Map::load
+    let mut map_file = filesystem::open(ctx, level_filename)?;
+    let mut map_string: String = "".to_string();
+    map_file.read_to_string(&mut map_string)?;
+    Ok(map_string)
+}
@@ -0,0 +1,226 @@
+use std::collections::VecDeque;
+use ggez::*;
+use ggez::audio::SoundSource;
+
+use crate::campaign::Campaign;
+use crate::gameplay::Gameplay;
+use crate::progress::Progress;
+use crate::bindings::Action;
+use crate::scene::{Scene, Shared, Transition};
+use crate::screen;
+use crate::ui::{self, MenuInput};
+
+const PANEL_OFFSET: f32 = 390.0; // koliko je kamen ispod svog konacnog polozaja na pocetku
+const ROW_HEIGHT: f32 = 40.0;
+const VISIBLE_ROWS: usize = 5; // vise nivoa ne staje na kamen, ostali se vide listanjem
+
+// Panel za izbor nivoa, isti kamen koji izlazi odozdo kao u MainMenu
+pub struct LevelSelect {
+    offset: f32, // trenutna udaljenost od konacnog polozaja, 0 kad je kamen gore
+    stone_pos: mint::Point2<f32>,
+    title: graphics::Text,
+    title_pos: mint::Point2<f32>,
+    levels: Vec<(String, graphics::Text)>, // fajl nivoa i tekst koji ga opisuje
+    levels_pos: mint::Point2<f32>,
+    first: usize, // prvi nivo koji se vidi
+    more: graphics::Text, // sledeca strana liste, samo ako svi nivoi ne staju
+    mouse_down: bool, // levo dugme je bilo pritisnuto u prethodnom updateu, drzanje ne lista dalje
+    back: graphics::Text,
+    stone: graphics::Image,
+    stone_sound: audio::Source,
+    closing: bool,
+    chosen: Option<String>, // fajl izabranog nivoa, None ako je izabrano "Back"
+    focus: Option<usize>, // red koji je zut: nivoi, pa Back
+    menu_input: VecDeque<MenuInput>, // sve sto je stiglo od proslog updatea, obradjuje se redom
+    last_mouse: mint::Point2<f32>,
+}
+
+impl LevelSelect {
+    pub fn new(ctx: &mut Context, campaign: &Campaign, progress: &Progress) -> GameResult<Self> {
+        let font_celtknot = graphics::Font::new(ctx, "/fonts/Celtknot.ttf")?;
+        let mut title = graphics::Text::new("Select Level");
+        title.set_font(font_celtknot, graphics::Scale::uniform(40.0));
+        let mut levels: Vec<(String, graphics::Text)> = Vec::new();
+        for file in campaign.level_files(ctx)? {
+            let record = progress.record(&file);
+            let mut text = graphics::Text::new(format!("{}   best {}{}", campaign.name_of(&file), record.best,
+                                                       if record.completed { "   completed" } else { "" }));
+            text.set_font(font_celtknot, graphics::Scale::uniform(26.0));
+            levels.push((file, text));
+        }
+        let mut more = graphics::Text::new("More...");
+        more.set_font(font_celtknot, graphics::Scale::uniform(26.0));
+        let mut back = graphics::Text::new("Back");
+        back.set_font(font_celtknot, graphics::Scale::uniform(30.0));
+        let mut stone_sound = audio::Source::new(ctx, "/sounds/stone_short.mp3")?;
+        stone_sound.play_detached()?;
+
+        Ok(LevelSelect {
+            offset: PANEL_OFFSET,
+            stone_pos: mint::Point2 { x: 40.0, y: 40.0 },
+            title: title,
+            title_pos: mint::Point2 { x: 170.0, y: 80.0 },
+            levels: levels,
+            levels_pos: mint::Point2 { x: 100.0, y: 160.0 },
+            first: 0,
+            more: more,
+            mouse_down: false,
+            back: back,
+            stone: graphics::Image::new(ctx, "/images/user_interface.png")?,
+            stone_sound: stone_sound,
+            closing: false,
+            chosen: None,
+            focus: None,
+            menu_input: VecDeque::new(),
+            last_mouse: screen::mouse_position(ctx),
+        })
+    }
+
+    fn shifted(&self, pos: mint::Point2<f32>) -> mint::Point2<f32> {
+        mint::Point2 { x: pos.x, y: pos.y + self.offset }
+    }
+
+    fn paged(&self) -> bool {
+        self.levels.len() > VISIBLE_ROWS
+    }
+
+    fn visible(&self) -> std::ops::Range<usize> {
+        self.first..(self.first + VISIBLE_ROWS).min(self.levels.len())
+    }
+
+    fn row(&self, i: usize) -> mint::Point2<f32> {
+        // i je nivo iz visible(), redovi se broje od prvog vidljivog
+        mint::Point2 { x: self.levels_pos.x, y: self.levels_pos.y + (i - self.first) as f32*ROW_HEIGHT }
+    }
+
+    fn more_pos(&self) -> mint::Point2<f32> {
+        mint::Point2 { x: self.levels_pos.x, y: self.levels_pos.y + VISIBLE_ROWS as f32*ROW_HEIGHT }
+    }
+
+    fn back_pos(&self) -> mint::Point2<f32> {
+        // Back je ispod poslednjeg reda, a kad se lista lista i ispod "More..."
+        let rows = if self.paged() { VISIBLE_ROWS + 1 } else { self.levels.len() };
+        mint::Point2 { x: 270.0, y: self.levels_pos.y + rows as f32*ROW_HEIGHT }
+    }
+
+    fn next_page(&mut self) {
+        // posle poslednje strane lista krece ispocetka
+        self.first += VISIBLE_ROWS;
+        if self.first >= self.levels.len() {
+            self.first = 0;
+        }
+    }
+
+    fn scroll_to(&mut self, i: usize) {
+        // lista se pomera tako da se fokusirani nivo vidi
+        if i < self.first {
+            self.first = i;
+        } else if i >= self.first + VISIBLE_ROWS {
+            self.first = i + 1 - VISIBLE_ROWS;
+        }
+    }
+
+    fn choose(&mut self, i: usize) {
+        // i je nivo, levels.len() je Back, a levels.len() + 1 je "More..."
+        if self.closing {
+            return
+        }
+        if i > self.levels.len() {
+            self.next_page();
+            return
+        }
+        self.chosen = self.levels.get(i).map(|(file, _)| file.clone());
+        self.closing = true;
+        self.stone_sound.play_detached().unwrap();
+    }
+}
+
+impl Scene for LevelSelect {
+    fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Transition> {
+        // kad se kamen vrati dole pocinje igra od izabranog nivoa, ili se vracamo u meni
+        if self.closing {
+            self.offset += 5.0;
+            if self.offset < PANEL_OFFSET {
+                return Ok(Transition::Stay)
+            }
+            return match &self.chosen {
+                Some(file) => Ok(Transition::Reset(Box::new(Gameplay::new(ctx, shared, Some(file))?))),
+                None => Ok(Transition::Pop),
+            }
+        }
+        if self.offset > 0.0 {
+            // dok kamen izlazi ne primamo klikove, da isti klik koji je otvorio panel ne izabere nivo
+            self.offset -= 5.0;
+            return Ok(Transition::Stay)
+        }
+        let mouse_down = input::mouse::button_pressed(ctx, input::mouse::MouseButton::Left);
+        let clicked = mouse_down && !self.mouse_down;
+        self.mouse_down = mouse_down;
+        let count = self.levels.len() + 1; // strelice idu kroz nivoe i Back, a lista se sama pomera
+        let (back, more) = (self.levels.len(), self.levels.len() + 1);
+        let mut hovered = self.visible().find(|&i| ui::hovered(ctx, &self.levels[i].1, self.row(i)));
+        if ui::hovered(ctx, &self.back, self.back_pos()) {
+            hovered = Some(back);
+        } else if self.paged() && ui::hovered(ctx, &self.more, self.more_pos()) {
+            hovered = Some(more);
+        }
+        let mouse_pos = screen::mouse_position(ctx);
+        if mouse_pos != self.last_mouse {
+            // mis se pomerio, fokus prati mis
+            self.last_mouse = mouse_pos;
+            self.focus = hovered;
+        }
+        while let Some(menu_input) = self.menu_input.pop_front() {
+            match menu_input {
+                MenuInput::Up => self.focus = Some(self.focus.filter(|f| *f < count).map_or(count - 1, |f| (f + count - 1) % count)),
+                MenuInput::Down => self.focus = Some(self.focus.filter(|f| *f < count).map_or(0, |f| (f + 1) % count)),
+                MenuInput::Confirm => if let Some(i) = self.focus {
+                    self.choose(i);
+                },
+                MenuInput::Back => self.choose(back),
+                _ => (),
+            }
+            if let Some(i) = self.focus.filter(|f| *f < back) {
+                self.scroll_to(i);
+            }
+        }
+        if clicked {
+            if let Some(i) = hovered {
+                self.choose(i);
+            }
+        }
+        for i in 0..self.levels.len() {
+            let on = self.focus == Some(i);
+            ui::highlight(&mut self.levels[i].1, on);
+        }
+        let on = self.focus == Some(back);
+        ui::highlight(&mut self.back, on);
+        let on = self.focus == Some(more);
+        ui::highlight(&mut self.more, on);
+        Ok(Transition::Stay)
+    }
+
+    fn draw(&self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<()> {
+        graphics::draw(ctx, &self.stone, graphics::DrawParam::new().dest(self.shifted(self.stone_pos)))?;
+        graphics::draw(ctx, &self.title, graphics::DrawParam::new().dest(self.shifted(self.title_pos)))?;
+        for i in self.visible() {
+            graphics::draw(ctx, &self.levels[i].1, graphics::DrawParam::new().dest(self.shifted(self.row(i))))?;
+        }
+        if self.paged() {
+            graphics::draw(ctx, &self.more, graphics::DrawParam::new().dest(self.shifted(self.more_pos())))?;
+        }
+        graphics::draw(ctx, &self.back, graphics::DrawParam::new().dest(self.shifted(self.back_pos())))?;
+        Ok(())
+    }
+
+    fn action(&mut self, action: Action) {
+        // Esc ili Start vraca u glavni meni, kao Back
+        if action == Action::Pause {
+            self.choose(self.levels.len());
+        }
+    }
+
+    fn menu(&mut self, input: MenuInput) {
+        self.menu_input.push_back(input);
+    }
+}
@@ -4,200 +4,257 @@ extern crate rand;
 extern crate nalgebra as na;
 
 mod map;
+mod noise;
+mod bindings;
+mod camera;
+mod gamepad;
+mod level;
+mod campaign;
 mod guard;
+mod pathfinding;
 mod anim;
 mod player;
 mod score;
 mod game_over;
 mod main_menu;
+mod level_select;
+mod progress;
+mod high_scores;
+mod high_score_panel;
+mod ui;
+mod pause_menu;
+mod controls_panel;
+mod scene;
+mod gameplay;
+mod replay;
+mod save_game;
+mod sim;
+mod render;
+mod screen;
 
 use ggez::*;
 use ggez::audio::SoundSource;
-use na::{Vector2, Isometry2};
-use ncollide2d::shape::{Cuboid, ShapeHandle};
-use ncollide2d::world::{CollisionGroups, CollisionWorld, GeometricQueryType};
-
-use crate::score::Score;
-use crate::anim::Direction;
-use crate::player::Player;
-use crate::game_over::GameOver;
+
+use crate::bindings::{Action, Bindings};
+use crate::campaign::{Campaign, CAMPAIGN_FILENAME};
+use crate::gamepad::GamepadState;
+use crate::gameplay::Gameplay;
 use crate::main_menu::MainMenu;
+use crate::progress::Progress;
+use crate::high_scores::HighScores;
+use crate::scene::{Scene, Shared, Transition};
+use crate::render::Renderer;
+use crate::replay::Replay;
+use crate::screen::{Screen, VIRTUAL_SIZE};
 
-use std::time::{Duration, Instant};
+use std::env;
 
-const SCREEN_SIZE: (f32, f32) = (
-    640.0, 480.0
-    );
+const UPDATES_PER_SECOND: f32 = sim::TICKS_PER_SECOND;
 
-const UPDATES_PER_SECOND: f32 = 30.0;
-const MILLIS_PER_UPDATE: u64 = (1.0 / UPDATES_PER_SECOND * 1000.0) as u64; // vreme koje treba da prodje izmedju dva updatea
+struct Options { // opcije sa komandne linije
+    seed: Option<u64>, // --seed <broj>
+    replay: Option<String>, // --replay <fajl>, igra odmah pusta snimak umesto menija
+}
+
+impl Options {
+    pub fn from_args() -> GameResult<Self> {
+        let mut options = Options { seed: None, replay: None };
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let value = args.next().unwrap_or_default();
+                    options.seed = match value.parse() {
+                        Ok(n) => Some(n),
+                        Err(_) => return Err(GameError::ConfigError(format!("--seed expects a number, found '{}'", value))),
+                    };
+                },
+                "--replay" => {
+                    match args.next() {
+                        Some(file) => options.replay = Some(file),
+                        None => return Err(GameError::ConfigError("--replay expects a file".to_string())),
+                    }
+                },
+                _ => return Err(GameError::ConfigError(format!("unknown argument '{}'", arg))),
+            }
+        }
+        Ok(options)
+    }
+}
 
 struct GameState { // glavno stanje cele igre
-    castle_map: map::Map,
-    player: Player,
-    world: CollisionWorld<f32, ()>,
-    last_update: Instant, // vreme kad se desio poslednji update
+    shared: Shared,
+    scenes: Vec<Box<dyn Scene>>, // stek ekrana, update i unos dobija samo poslednji
     song: audio::Source,
-    menu: MainMenu,
-    in_menu: bool,
-    end: Option<GameOver>,
+    screen: Screen, // platno na koje se sve crta, pa se uvecava na velicinu prozora
 }
 
 impl GameState {
-    pub fn new(ctx: &mut Context) -> GameResult<Self> {
-        let mut world_mut = CollisionWorld::new(0.02);
-        let shape = ShapeHandle::new(Cuboid::new(Vector2::new(12.0, 8.0)));
-        let mut groups = CollisionGroups::new();
-        groups.set_membership(&[0 as usize]);
-        groups.set_blacklist(&[0 as usize]);
-        groups.set_whitelist(&[1 as usize]);
-        let query = GeometricQueryType::Contacts(0.0, 0.0);
+    pub fn new(ctx: &mut Context, options: Options) -> GameResult<Self> {
         let celtic_song = audio::Source::new(ctx, "/music/a_celtic_lore.mp3")?;
+        let bindings = Bindings::load(ctx);
+        let mut shared = Shared {
+            campaign: Campaign::load(ctx, CAMPAIGN_FILENAME)?,
+            progress: Progress::load(ctx),
+            high_scores: HighScores::load(ctx),
+            renderer: Renderer::new(ctx)?,
+            seed: options.seed,
+            music_on: true,
+            bindings: bindings,
+            gamepad: GamepadState::default(),
+        };
 
+        let first: Box<dyn Scene> = match options.replay {
+            Some(ref file) => Box::new(Gameplay::from_replay(ctx, &mut shared, Replay::load(file)?)?),
+            None => Box::new(MainMenu::new(ctx, &shared.bindings)?),
+        };
 
         Ok(GameState {
-            castle_map: map::Map::load(ctx, "/levels/level1.txt", "/images/castle_spritesheet.png", mint::Point2 { x:100.0, y:164.0 }, mint::Point2 { x:32.0, y:32.0 }, &mut world_mut).unwrap(),
-            player: Player::new(ctx, world_mut.add(Isometry2::new(Vector2::new(64.0, 74.0), 0.0), shape.clone(), groups, query, ()).handle()),
-            world: world_mut,
-            last_update: Instant::now(),
+            shared: shared,
+            scenes: vec![first],
             song: celtic_song,
-            menu: MainMenu::new(ctx)?,
-            in_menu: true,
-            end: None,
+            screen: Screen::new(ctx)?,
         })
     }
 
-    pub fn reset(&mut self, ctx: &mut Context) -> GameResult<()> {
-        let new_state = GameState::new(ctx)?;
-        self.castle_map = new_state.castle_map;
-        self.player = new_state.player;
-        self.world = new_state.world;
-        //self.last_update = new_state.last_update;
-        //self.song = new_state.song;
-        self.menu = new_state.menu;
-        self.in_menu = new_state.in_menu;
-        self.end = new_state.end;
+    fn apply(&mut self, ctx: &mut Context, transition: Transition) -> GameResult<()> {
+        match transition {
+            Transition::Stay => (),
+            Transition::Push(scene) => self.scenes.push(scene),
+            Transition::Pop => {
+                self.scenes.pop();
+            },
+            Transition::PopWith(signal) => {
+                self.scenes.pop();
+                if let Some(scene) = self.scenes.last_mut() {
+                    scene.signal(ctx, &mut self.shared, signal)?;
+                }
+            },
+            Transition::Replace(scene) => {
+                self.scenes.pop();
+                self.scenes.push(scene);
+            },
+            Transition::Reset(scene) => {
+                self.scenes.clear();
+                self.scenes.push(scene);
+            },
+        }
         Ok(())
     }
 }
 
 impl event::EventHandler for GameState {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
-        // da kontrolisemo broj apdejta u sekundi, ili FPS
-        if Instant::now() - self.last_update >= Duration::from_millis(MILLIS_PER_UPDATE) {
-            if !self.song.playing() {
+        // ggez skuplja proteklo vreme, a mi trosimo po 1/UPDATES_PER_SECOND sekunde na svaki tick.
+        // Ako je frejm trajao duze, u istom pozivu se odradi vise tickova da igra ne bi usporila
+        self.shared.gamepad.forget_disconnected(|id| input::gamepad::gamepad(ctx, id).is_connected());
+        while timer::check_update_time(ctx, UPDATES_PER_SECOND as u32) {
+            let transition = match self.scenes.last_mut() {
+                Some(scene) => scene.update(ctx, &mut self.shared)?,
+                None => Transition::Stay,
+            };
+            self.apply(ctx, transition)?;
+        }
+        // muzika stoji dok je igra pauzirana ili ako je iskljucena u opcijama
+        let want_music = self.shared.music_on && !self.scenes.last().map_or(false, |s| s.pauses_music());
+        if want_music && !self.song.playing() {
+            if self.song.paused() {
+                self.song.resume();
+            } else {
                 self.song.set_repeat(true);
                 self.song.play()?;
             }
-            if !self.in_menu {
-                match &mut self.end {
-                    None => {
-                        if self.player.caught {
-                            self.end = Some(GameOver::new(ctx, self.player.score, false).unwrap());
-                        } else if self.player.escaped {
-                            self.end = Some(GameOver::new(ctx, self.player.score, true).unwrap());
-                        }
-                        let map_move = self.player.update(ctx, &mut self.world, self.castle_map.map_handle, &mut self.castle_map.get_corners());
-                        self.castle_map.update(&mut self.world, map_move);
-                        self.world.update();
-                        self.player.caught = self.castle_map.update_guards(&mut self.world, self.player.col_handle);
-                        self.player.escaped = self.castle_map.map_door.update(ctx, &mut self.world, self.player.col_handle, self.castle_map.map_vel);
-                        self.player.increase(self.castle_map.update_gold(&mut self.world, self.player.col_handle))?;
-                    },
-                    Some(g) => {
-                        match g.update(ctx) {
-                            true=> (),
-                            false => {
-                                self.reset(ctx)?;
-                                ()
-                            }
-                        }
-                    }
-                }
-            } else {
-                self.in_menu = self.menu.update(ctx);
-            }
-            self.last_update = Instant::now();
+        } else if !want_music && self.song.playing() {
+            self.song.pause();
         }
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult { // crta sve na mapu, bitan je redosled navodjenja pojedinacnih draw funkcija
+        self.screen.begin(ctx)?;
         graphics::clear(ctx, (18, 15, 17, 255).into()); // brise prethodno stanje ekrana (posto se ono non stop updateuje)
-        if !self.in_menu {
-            match &self.end {
-                None => {
-                    self.castle_map.draw(ctx, 1, false)?; // crta prvi sloj mape (podovi, zidovi iza igraca)
-                    self.castle_map.map_door.draw(ctx)?; // crta vrata
-                    self.castle_map.draw_gold(ctx)?; // prodje kroz ceo vektor i nacrta svaki element
-                    self.player.draw(ctx, false)?;
-                    self.castle_map.draw_guards(ctx)?;
-                    self.castle_map.draw(ctx, 2, false)?; // crta drugi sloj mape (donji zidovi)
-                    self.castle_map.draw_guard_vision(ctx)?; // vidno polje strazara
-                    self.player.draw_score(ctx)?;
-                    // self.player.draw_visibility(ctx)?;
-                },
-                Some(g) => {
-                    g.draw(ctx)?;
-                }
-            }
-        } else {
-            self.menu.draw(ctx)?;
+        // crtamo od prve scene koja nije overlay, pa navise (npr. igra pa pauza preko nje)
+        let mut first = self.scenes.len().saturating_sub(1);
+        while first > 0 && self.scenes[first].is_overlay() {
+            first -= 1;
         }
+        for scene in self.scenes.iter().skip(first) {
+            scene.draw(ctx, &mut self.shared)?;
+        }
+        self.screen.finish(ctx)?;
         graphics::present(ctx)?; // konacno sve nacrta na ekran
-        timer::yield_now(); // ovo pisemo da bi crtanje sacekalo sledeci update
         Ok(())
     }
 
     fn key_down_event(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context,
         keycode: event::KeyCode,
         _keymod: event::KeyMods,
         _repeat: bool,
         ) {
-        match keycode {
-            event::KeyCode::Up if self.player.collision_ver != Direction::Up => {
-                self.player.direction.y = -1.0;
-                self.player.animation_state = Direction::Up;
-            },
-            event::KeyCode::Down if self.player.collision_ver != Direction::Down => {
-                self.player.direction.y = 1.0;
-                self.player.animation_state = Direction::Down;
-            },
-            event::KeyCode::Left if self.player.collision_hor != Direction::Left => {
-                self.player.direction.x = -1.0;
-                self.player.animation_state = Direction::Left;
-            },
-            event::KeyCode::Right if self.player.collision_hor != Direction::Right => {
-                self.player.direction.x = 1.0;
-                self.player.animation_state = Direction::Right;
-            },
-            _ => (),
+        // scena dobija i sam taster (Backspace pri upisu inicijala, promena tastera),
+        // akcije na koje je taster vezan (npr. Pause) i kretanje kroz meni
+        let actions = self.shared.bindings.actions(keycode);
+        if let Some(scene) = self.scenes.last_mut() {
+            scene.key_down(keycode);
+            for action in actions.iter() {
+                scene.action(*action);
+            }
+            if let Some(menu_input) = ui::menu_input(keycode) {
+                scene.menu(menu_input);
+            }
+        }
+        if actions.contains(&Action::TogglePaths) {
+            self.shared.renderer.show_paths = !self.shared.renderer.show_paths; // debug prikaz putanja strazara
+        }
+        if actions.contains(&Action::ZoomIn) {
+            self.shared.renderer.zoom_in();
+        }
+        if actions.contains(&Action::ZoomOut) {
+            self.shared.renderer.zoom_out();
+        }
+        if actions.contains(&Action::ToggleFullscreen) {
+            // ako prozor ne moze preko celog ekrana, ostaje kakav je
+            self.screen.toggle_fullscreen(ctx).ok();
         }
     }
-    fn key_up_event(
-        &mut self,
-        _ctx: &mut Context,
-        keycode: event::KeyCode,
-        _keymod: event::KeyMods,
-        ) {
-        match keycode {
-            event::KeyCode::Up if self.player.direction.y == -1.0 => self.player.direction.y = 0.0,
-            event::KeyCode::Down if self.player.direction.y == 1.0 => self.player.direction.y = 0.0,
-            event::KeyCode::Left if self.player.direction.x == -1.0 => self.player.direction.x = 0.0,
-            event::KeyCode::Right if self.player.direction.x == 1.0 => self.player.direction.x = 0.0,
-            _ => (),
+
+    fn gamepad_button_down_event(&mut self, _ctx: &mut Context, btn: event::Button, id: input::gamepad::GamepadId) {
+        // kretanje u igri se cita iz shared.gamepad, a sceni se salju akcije i kretanje kroz meni
+        self.shared.gamepad.button_down(id, btn);
+        if let Some(scene) = self.scenes.last_mut() {
+            if let Some(action) = gamepad::action(btn) {
+                scene.action(action);
+            }
+            if let Some(menu_input) = gamepad::menu_input(btn) {
+                scene.menu(menu_input);
+            }
+        }
+    }
+
+    fn gamepad_button_up_event(&mut self, _ctx: &mut Context, btn: event::Button, id: input::gamepad::GamepadId) {
+        self.shared.gamepad.button_up(id, btn);
+    }
+
+    fn gamepad_axis_event(&mut self, _ctx: &mut Context, axis: event::Axis, value: f32, id: input::gamepad::GamepadId) {
+        self.shared.gamepad.axis(id, axis, value);
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) {
+        if let Some(scene) = self.scenes.last_mut() {
+            scene.text_input(character);
         }
     }
 }
 
 fn main() -> GameResult {
+        let options = Options::from_args()?;
         let (ctx, events_loop) = &mut ContextBuilder::new("robin_hood", "lkh01, marrijana")
            .window_setup(conf::WindowSetup::default().title("Robin Hood"))
-           .window_mode(conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+           .window_mode(conf::WindowMode::default().dimensions(VIRTUAL_SIZE.0, VIRTUAL_SIZE.1).resizable(true))
            .build()?;
 
-        let state = &mut GameState::new(ctx)?;
+        let state = &mut GameState::new(ctx, options)?;
         event::run(ctx, events_loop, state)
 }
@@ -1,42 +1,94 @@
+use std::collections::VecDeque;
 use ggez::*;
 use ggez::audio::SoundSource;
 
+use crate::bindings::Bindings;
+use crate::gameplay::Gameplay;
+use crate::high_score_panel::HighScorePanel;
+use crate::level_select::LevelSelect;
+use crate::save_game::SaveGame;
+use crate::scene::{Scene, Shared, Transition};
+use crate::screen;
+use crate::ui::{self, MenuInput};
+
+const SLIDE: f32 = 390.0; // koliko su kamen i tekstovi nize dok se kamen ne podigne, deljivo sa 5
+
+// Stavke menija redom odozgo na dole
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MenuItem {
+    Continue,
+    Play,
+    Levels,
+    Records,
+    Quit,
+}
+
 pub struct MainMenu {
     text1_pos: mint::Point2<f32>,
     text2_pos: mint::Point2<f32>,
     text3_pos: mint::Point2<f32>,
     text4_pos: mint::Point2<f32>,
+    text5_pos: mint::Point2<f32>,
+    text6_pos: mint::Point2<f32>,
+    text7_pos: mint::Point2<f32>,
     stone_pos: mint::Point2<f32>,
     text1: graphics::Text,
     text2: graphics::Text,
     text3: graphics::Text,
     text4: graphics::Text,
+    text5: graphics::Text,
+    text6: graphics::Text,
+    text7: graphics::Text,
     stone: graphics::Image,
+    font: graphics::Font,
     final_text1_pos: mint::Point2<f32>,
     final_text2_pos: mint::Point2<f32>,
     final_text3_pos: mint::Point2<f32>,
     final_text4_pos: mint::Point2<f32>,
+    final_text5_pos: mint::Point2<f32>,
+    final_text6_pos: mint::Point2<f32>,
+    final_text7_pos: mint::Point2<f32>,
     final_stone_pos: mint::Point2<f32>,
     orig_text1_pos: mint::Point2<f32>,
     orig_text2_pos: mint::Point2<f32>,
     orig_text3_pos: mint::Point2<f32>,
     orig_text4_pos: mint::Point2<f32>,
+    orig_text5_pos: mint::Point2<f32>,
+    orig_text6_pos: mint::Point2<f32>,
+    orig_text7_pos: mint::Point2<f32>,
     orig_stone_pos: mint::Point2<f32>,
     stone_sound: audio::Source,
     play: bool,
+    can_continue: bool, // postoji igra sacuvana iz pauze
+    resume: bool, // kamen se spusta zbog Continue, a ne zbog Play
+    focus: Option<MenuItem>, // stavka koja je zuta, bira se misem, strelicama ili sa kontrolera
+    menu_input: VecDeque<MenuInput>, // sve sto je stiglo od proslog updatea, obradjuje se redom
+    last_mouse: mint::Point2<f32>,
 
 }
 
 impl MainMenu {
-    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+    pub fn new(ctx: &mut Context, bindings: &Bindings) -> GameResult<Self> {
         let mut title_text = graphics::Text::new("Robin Hood");
         let mut play_text = graphics::Text::new("Play");
         let mut quit_text = graphics::Text::new("Quit");
-        let mut help_text = graphics::Text::new("Controls: Arrow keys to move,\n\n  E to exit through the door");
+        let mut levels_text = graphics::Text::new("Levels");
+        let mut records_text = graphics::Text::new("Records");
+        let mut continue_text = graphics::Text::new("Continue");
+        let mut help_text = graphics::Text::new(bindings.help_text()); // tasteri se mogu promeniti, pa se tekst pravi od njih
         let font_celtknot = graphics::Font::new(ctx, "/fonts/Celtknot.ttf").unwrap();
         title_text.set_font(font_celtknot, graphics::Scale::uniform(60.0));
         play_text.set_font(font_celtknot, graphics::Scale::uniform(40.0));
         quit_text.set_font(font_celtknot, graphics::Scale::uniform(40.0));
+        levels_text.set_font(font_celtknot, graphics::Scale::uniform(40.0));
+        records_text.set_font(font_celtknot, graphics::Scale::uniform(40.0));
+        continue_text.set_font(font_celtknot, graphics::Scale::uniform(40.0));
+        let can_continue = SaveGame::exists(ctx);
+        if !can_continue {
+            for fragment in continue_text.fragments_mut() {
+                fragment.color = Some([0.5, 0.5, 0.5, 1.0].into()); // nema sta da se nastavi
+            }
+        }
         help_text.set_font(font_celtknot, graphics::Scale::uniform(26.0));
         for fragment in help_text.fragments_mut() {
             fragment.color = Some((208, 198, 29, 255).into());
@@ -44,34 +96,150 @@ impl MainMenu {
         let mut stone_sound = audio::Source::new(ctx, "/sounds/stone_short.mp3").unwrap();
 
         stone_sound.play_detached()?;
+        let stone = graphics::Image::new(ctx, "/images/user_interface.png").unwrap();
+
+        // polozaji su na platnu: kamen i tekstovi su centrirani po sirini, a pre
+        // podizanja kamena su za SLIDE nize, van ekrana
+        let final_text1_pos = mint::Point2 { x: screen::centered_x(ctx, &title_text), y: 100.0 };
+        let final_text2_pos = mint::Point2 { x: screen::centered_x(ctx, &play_text), y: 205.0 };
+        let final_text3_pos = mint::Point2 { x: screen::centered_x(ctx, &quit_text), y: 340.0 };
+        let final_text4_pos = mint::Point2 { x: screen::centered_x(ctx, &help_text), y: 385.0 };
+        let final_text5_pos = mint::Point2 { x: screen::centered_x(ctx, &levels_text), y: 250.0 };
+        let final_text6_pos = mint::Point2 { x: screen::centered_x(ctx, &records_text), y: 295.0 };
+        let final_text7_pos = mint::Point2 { x: screen::centered_x(ctx, &continue_text), y: 160.0 };
+        let final_stone_pos = mint::Point2 { x: (screen::VIRTUAL_SIZE.0 - stone.width() as f32)/2.0, y: 40.0 };
+        let below = |p: mint::Point2<f32>| mint::Point2 { x: p.x, y: p.y + SLIDE };
 
         Ok(MainMenu {
-            text1_pos: mint::Point2 { x: 150.0, y: 542.0 },
-            text2_pos: mint::Point2 { x: 260.0, y: 642.0 },
-            text3_pos: mint::Point2 { x: 260.0, y: 702.0 },
-            text4_pos: mint::Point2 { x: 135.0, y: 762.0 },
-            stone_pos: mint::Point2 { x: 40.0, y: 430.0 },
+            text1_pos: below(final_text1_pos),
+            text2_pos: below(final_text2_pos),
+            text3_pos: below(final_text3_pos),
+            text4_pos: below(final_text4_pos),
+            text5_pos: below(final_text5_pos),
+            text6_pos: below(final_text6_pos),
+            text7_pos: below(final_text7_pos),
+            stone_pos: below(final_stone_pos),
             text1: title_text,
             text2: play_text,
             text3: quit_text,
             text4: help_text,
-            stone: graphics::Image::new(ctx, "/images/user_interface.png").unwrap(),
-            final_text1_pos: mint::Point2 { x: 150.0, y: 152.0 },
-            final_text2_pos: mint::Point2 { x: 260.0, y: 252.0 },
-            final_text3_pos: mint::Point2 { x: 260.0, y: 312.0 },
-            final_text4_pos: mint::Point2 { x: 135.0, y: 372.0 },
-            final_stone_pos: mint::Point2 { x: 40.0, y: 40.0 },
-            orig_text1_pos: mint::Point2 { x: 150.0, y: 542.0 },
-            orig_text2_pos: mint::Point2 { x: 260.0, y: 642.0 },
-            orig_text3_pos: mint::Point2 { x: 260.0, y: 702.0 },
-            orig_text4_pos: mint::Point2 { x: 135.0, y: 762.0 },
-            orig_stone_pos: mint::Point2 { x: 40.0, y: 430.0 },
+            text5: levels_text,
+            text6: records_text,
+            text7: continue_text,
+            stone: stone,
+            font: font_celtknot,
+            final_text1_pos: final_text1_pos,
+            final_text2_pos: final_text2_pos,
+            final_text3_pos: final_text3_pos,
+            final_text4_pos: final_text4_pos,
+            final_text5_pos: final_text5_pos,
+            final_text6_pos: final_text6_pos,
+            final_text7_pos: final_text7_pos,
+            final_stone_pos: final_stone_pos,
+            orig_text1_pos: below(final_text1_pos),
+            orig_text2_pos: below(final_text2_pos),
+            orig_text3_pos: below(final_text3_pos),
+            orig_text4_pos: below(final_text4_pos),
+            orig_text5_pos: below(final_text5_pos),
+            orig_text6_pos: below(final_text6_pos),
+            orig_text7_pos: below(final_text7_pos),
+            orig_stone_pos: below(final_stone_pos),
             stone_sound: stone_sound,
             play: false,
+            can_continue: can_continue,
+            resume: false,
+            focus: None,
+            menu_input: VecDeque::new(),
+            last_mouse: screen::mouse_position(ctx),
+        })
+    }
+
+    fn items(&self) -> Vec<MenuItem> {
+        // Continue se preskace ako nema sacuvane igre
+        let mut items = vec![MenuItem::Play, MenuItem::Levels, MenuItem::Records, MenuItem::Quit];
+        if self.can_continue {
+            items.insert(0, MenuItem::Continue);
+        }
+        items
+    }
+
+    fn text_mut(&mut self, item: MenuItem) -> &mut graphics::Text {
+        match item {
+            MenuItem::Continue => &mut self.text7,
+            MenuItem::Play => &mut self.text2,
+            MenuItem::Levels => &mut self.text5,
+            MenuItem::Records => &mut self.text6,
+            MenuItem::Quit => &mut self.text3,
+        }
+    }
+
+    fn hovered_item(&self, ctx: &mut Context) -> Option<MenuItem> {
+        self.items().into_iter().find(|item| {
+            let (text, pos) = match item {
+                MenuItem::Continue => (&self.text7, self.text7_pos),
+                MenuItem::Play => (&self.text2, self.text2_pos),
+                MenuItem::Levels => (&self.text5, self.text5_pos),
+                MenuItem::Records => (&self.text6, self.text6_pos),
+                MenuItem::Quit => (&self.text3, self.text3_pos),
+            };
+            ui::hovered(ctx, text, pos)
         })
     }
 
-    pub fn update(&mut self, ctx: &mut Context) -> bool {
+    fn next_item(&self, step: i32) -> MenuItem {
+        // sledeca ili prethodna stavka u krug; bez fokusa krecemo od prve ili poslednje
+        let items = self.items();
+        let count = items.len() as i32;
+        let index = match self.focus.and_then(|f| items.iter().position(|i| *i == f)) {
+            Some(i) => (i as i32 + step + count) % count,
+            None => if step > 0 { 0 } else { count - 1 },
+        };
+        items[index as usize]
+    }
+
+    fn continue_failed(&mut self, ctx: &mut Context, error: &GameError) {
+        // sacuvana igra je ostecena ili ne odgovara kampanji: brisemo je da ne bi pukla i sledeci put,
+        // a meni se vraca gore sa porukom na mestu za Continue
+        eprintln!("Could not continue the saved game: {}", error);
+        SaveGame::delete(ctx).ok();
+        self.can_continue = false;
+        self.play = false;
+        self.resume = false;
+        self.focus = Some(MenuItem::Play);
+        let mut notice = graphics::Text::new("Saved game could not be loaded");
+        notice.set_font(self.font, graphics::Scale::uniform(26.0));
+        for fragment in notice.fragments_mut() {
+            fragment.color = Some([0.5, 0.5, 0.5, 1.0].into());
+        }
+        let x = screen::centered_x(ctx, &notice);
+        self.text7 = notice;
+        self.text7_pos.x = x;
+        self.final_text7_pos.x = x;
+        self.orig_text7_pos.x = x;
+    }
+
+    fn activate(&mut self, ctx: &mut Context, shared: &mut Shared, item: MenuItem) -> GameResult<Transition> {
+        // paneli se otvaraju tek kad kamen stigne gore
+        let settled = self.stone_pos == self.final_stone_pos;
+        match item {
+            MenuItem::Continue | MenuItem::Play => {
+                self.play = true;
+                self.resume = item == MenuItem::Continue;
+                self.stone_sound.play_detached().unwrap();
+            },
+            MenuItem::Levels if settled => return Ok(Transition::Push(Box::new(LevelSelect::new(ctx, &shared.campaign, &shared.progress)?))),
+            MenuItem::Records if settled => return Ok(Transition::Push(Box::new(HighScorePanel::new(ctx, &shared.campaign, &shared.high_scores)?))),
+            MenuItem::Quit => quit(ctx),
+            _ => (),
+        }
+        Ok(Transition::Stay)
+    }
+}
+
+impl Scene for MainMenu {
+    fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Transition> {
+        // paneli za izbor nivoa i rekorde su posebne scene koje se stavljaju preko menija
+        let mut transition = Transition::Stay;
         if self.play == false {
             if self.text1_pos != self.final_text1_pos {
                 self.text1_pos.y -= 5.0;
@@ -85,39 +253,50 @@ impl MainMenu {
             if self.text4_pos != self.final_text4_pos {
                 self.text4_pos.y -= 5.0;
             }
+            if self.text5_pos != self.final_text5_pos {
+                self.text5_pos.y -= 5.0;
+            }
+            if self.text6_pos != self.final_text6_pos {
+                self.text6_pos.y -= 5.0;
+            }
+            if self.text7_pos != self.final_text7_pos {
+                self.text7_pos.y -= 5.0;
+            }
             if self.stone_pos != self.final_stone_pos {
                 self.stone_pos.y -= 5.0;
             }
-            let mouse_pos = input::mouse::position(ctx);
-            if mouse_pos.x >= self.text2_pos.x && mouse_pos.x < self.text2_pos.x + (self.text2.dimensions(ctx).0 as f32) &&
-                mouse_pos.y >= self.text2_pos.y && mouse_pos.y < self.text2_pos.y + (self.text2.dimensions(ctx).1 as f32) {
-                // mis je preko play teksta
-                for fragment in self.text2.fragments_mut() {
-                    fragment.color = Some((208, 198, 29, 255).into());
-                }
-                if input::mouse::button_pressed(ctx, input::mouse::MouseButton::Left) {
-                    self.play = true;
-                    self.stone_sound.play_detached().unwrap();
-                }
-            } else {
-                for fragment in self.text2.fragments_mut() {
-                    fragment.color = Some([1.0, 1.0, 1.0, 1.0].into());
-                }
+            let hovered = self.hovered_item(ctx);
+            let mouse_pos = screen::mouse_position(ctx);
+            if mouse_pos != self.last_mouse {
+                // mis se pomerio, fokus prati mis
+                self.last_mouse = mouse_pos;
+                self.focus = hovered;
             }
-            if mouse_pos.x >= self.text3_pos.x && mouse_pos.x < self.text3_pos.x + (self.text3.dimensions(ctx).0 as f32) &&
-                mouse_pos.y >= self.text3_pos.y && mouse_pos.y < self.text3_pos.y + (self.text3.dimensions(ctx).1 as f32) {
-                // mis je preko play teksta
-                for fragment in self.text3.fragments_mut() {
-                    fragment.color = Some((208, 198, 29, 255).into());
+            while let Some(menu_input) = self.menu_input.pop_front() {
+                match menu_input {
+                    MenuInput::Up => self.focus = Some(self.next_item(-1)),
+                    MenuInput::Down => self.focus = Some(self.next_item(1)),
+                    MenuInput::Confirm => if let Some(item) = self.focus {
+                        transition = self.activate(ctx, shared, item)?;
+                    },
+                    MenuInput::Back => self.focus = Some(MenuItem::Quit), // Esc pa Enter izlazi iz igre
+                    _ => (),
                 }
-                if input::mouse::button_pressed(ctx, input::mouse::MouseButton::Left) {
-                    quit(ctx);
+                match transition {
+                    Transition::Stay => (),
+                    _ => self.menu_input.clear(), // panel koji se otvara ne nasledjuje ostatak unosa
                 }
-            } else {
-                for fragment in self.text3.fragments_mut() {
-                    fragment.color = Some([1.0, 1.0, 1.0, 1.0].into());
+            }
+            if input::mouse::button_pressed(ctx, input::mouse::MouseButton::Left) {
+                if let Some(item) = hovered {
+                    transition = self.activate(ctx, shared, item)?;
                 }
             }
+            for item in self.items() {
+                // isto zuto za mis i za fokus sa tastature ili kontrolera
+                let on = self.focus == Some(item);
+                ui::highlight(self.text_mut(item), on);
+            }
         } else {
             if self.text1_pos != self.orig_text1_pos {
                 self.text1_pos.y += 5.0;
@@ -131,22 +310,47 @@ impl MainMenu {
             if self.text4_pos != self.orig_text4_pos {
                 self.text4_pos.y += 5.0;
             }
+            if self.text5_pos != self.orig_text5_pos {
+                self.text5_pos.y += 5.0;
+            }
+            if self.text6_pos != self.orig_text6_pos {
+                self.text6_pos.y += 5.0;
+            }
+            if self.text7_pos != self.orig_text7_pos {
+                self.text7_pos.y += 5.0;
+            }
             if self.stone_pos != self.orig_stone_pos {
                 self.stone_pos.y += 5.0;
             }
             if self.stone_pos == self.orig_stone_pos {
-                return false
+                if self.resume {
+                    match SaveGame::load(ctx).and_then(|save| Gameplay::resume(ctx, shared, save)) {
+                        Ok(game) => return Ok(Transition::Reset(Box::new(game))),
+                        Err(e) => {
+                            self.continue_failed(ctx, &e);
+                            return Ok(Transition::Stay)
+                        },
+                    }
+                }
+                return Ok(Transition::Reset(Box::new(Gameplay::new(ctx, shared, None)?)))
             }
         }
-        true
+        Ok(transition)
     }
 
-    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+    fn draw(&self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<()> {
         graphics::draw(ctx, &self.stone, graphics::DrawParam::new().dest(self.stone_pos))?;
         graphics::draw(ctx, &self.text1, graphics::DrawParam::new().dest(self.text1_pos))?;
         graphics::draw(ctx, &self.text2, graphics::DrawParam::new().dest(self.text2_pos))?;
         graphics::draw(ctx, &self.text3, graphics::DrawParam::new().dest(self.text3_pos))?;
         graphics::draw(ctx, &self.text4, graphics::DrawParam::new().dest(self.text4_pos))?;
+        graphics::draw(ctx, &self.text5, graphics::DrawParam::new().dest(self.text5_pos))?;
+        graphics::draw(ctx, &self.text6, graphics::DrawParam::new().dest(self.text6_pos))?;
+        graphics::draw(ctx, &self.text7, graphics::DrawParam::new().dest(self.text7_pos))?;
         Ok(())
     }
+
+    fn menu(&mut self, input: MenuInput) {
+        self.menu_input.push_back(input);
+    }
 }
@@ -1,13 +1,25 @@
-use std::io::Read;
 use std::f32::consts::PI;
-use std::path::Path;
 use ggez::*;
 use na::{Vector2, Isometry2, Point2};
 use ncollide2d::shape::{Cuboid, Compound, ConvexPolygon, ShapeHandle};
 use ncollide2d::world::{CollisionGroups, CollisionObjectHandle, CollisionWorld, GeometricQueryType};
-use rand::{thread_rng, Rng};
+use ncollide2d::query::{Ray, RayCast};
+use rand::Rng;
+use rand::rngs::StdRng;
 
-use crate::guard::Guard;
+use crate::guard::{Alertness, Guard, RouteMode, Waypoint};
+use crate::level::{LevelError, split_fields, parse_field};
+use crate::noise::{Noise, NoiseBus, NoiseKind};
+use crate::pathfinding::{Grid, TilePos};
+use crate::sim::TICKS_PER_SECOND;
+
+pub const TILE_SIZE: f32 = 32.0; // polja su kvadrati 32x32 piksela
+const MAX_MAP_SIZE: f32 = 1000.0; // najveca sirina i visina iz zaglavlja, da los fajl ne bi zauzeo svu memoriju
+const DOOR_NOISE: f32 = 160.0; // koliko daleko se cuju zvuci, kao radius iz Noise
+const COINS_NOISE: f32 = 96.0;
+const PEBBLE_NOISE: f32 = 128.0;
+const PEBBLE_SPD: f32 = 8.0;
+const PEBBLE_TICKS: i32 = 20; // koliko dugo kamencic leti ako ne udari u zid, najvise 160 piksela
 
 
 #[derive(Debug, Clone)]
@@ -18,11 +30,11 @@ pub enum TileType {
 
 #[derive(Debug, Clone)] // Clone nam treba da bi mogli da kopiramo vektore
 pub struct Tile {
-    tile_type: TileType,
+    pub tile_type: TileType,
     tile_src: graphics::DrawParam,
     pub tile_layer: i32,
     tile_pos: mint::Point2<f32>,
-    tile_size: mint::Point2<f32>,
+    pub tile_size: mint::Point2<f32>,
 }
 
 impl Tile {
@@ -53,65 +65,102 @@ impl Tile {
 }
 
 pub struct Door {
-    pos: mint::Point2<f32>,
-    image: graphics::Image,
+    pub pos: mint::Point2<f32>,
     handle: CollisionObjectHandle,
+    touched: bool, // igrac je u prethodnom ticku dodirivao vrata
 }
 impl Door {
-    pub fn new (ctx: &mut Context, door_pos: mint::Point2<f32>, col_handle: CollisionObjectHandle) -> Self {
+    pub fn new (door_pos: mint::Point2<f32>, col_handle: CollisionObjectHandle) -> Self {
         // handle ce nam kasnije pomoci da odredimo da li je igrac dodirnuo vrata
         Door {
             pos: door_pos,
-            image: graphics::Image::new(ctx, "/images/castle_door.png").unwrap(),
             handle: col_handle,
+            touched: false,
         }
     }
-    pub fn update(&mut self, ctx: &mut Context, world: &mut CollisionWorld<f32, ()>, player_handle: CollisionObjectHandle, map_vel: Vector2<f32>) -> bool {
-        self.pos.x += map_vel.x;
-        self.pos.y += map_vel.y;
+    pub fn place(&self, world: &mut CollisionWorld<f32, ()>) {
+        // vrata se ne pomeraju, oblik se postavlja samo jednom pri ucitavanju
         world.set_position(self.handle, Isometry2::new(Vector2::new(self.pos.x, self.pos.y), 0.0));
-        match world.contact_pair(self.handle, player_handle, true) {
-            // contact_pair vraca uredjenu cetvorku koja opisuje da li se desio sudar
-            Some(_) if input::keyboard::is_key_pressed(ctx, event::KeyCode::E) => true,
-            _ => false,
+    }
+
+    pub fn update(&mut self, world: &mut CollisionWorld<f32, ()>, player_handle: CollisionObjectHandle, interact: bool, noises: &mut NoiseBus) -> bool {
+        // contact_pair vraca uredjenu cetvorku koja opisuje da li se desio sudar
+        let touching = world.contact_pair(self.handle, player_handle, true).is_some();
+        if touching && !self.touched {
+            // vrata zaskripe cim ih igrac dodirne, pa strazari stignu da reaguju pre nego sto izadje.
+            // Zvuk je tamo gde igrac stoji, jer je polje vrata u zidu i zid bi ga zaklonio
+            let player_center = world.collision_object(player_handle).unwrap().position().translation.vector;
+            noises.publish(Noise { pos: mint::Point2 { x: player_center.x, y: player_center.y }, radius: DOOR_NOISE, kind: NoiseKind::Door });
         }
+        self.touched = touching;
+        touching && interact
     }
-    pub fn draw (&self, ctx: &mut Context) -> GameResult<()> {
-        graphics::draw(ctx, &self.image, graphics::DrawParam::new().dest(self.pos))?;
-        Ok(())
+}
+
+// Vrsta blaga koje moze da se nadje na mapi. Nivo moze da definise nove
+// vrste linijom "treasure", a gold1..3 postoje uvek
+#[derive(Debug, Clone)]
+pub struct Treasure {
+    pub name: String,
+    pub value: i32,
+    pub sprite: String, // putanja do slike u resources, renderer je ucitava
+}
+
+impl Treasure {
+    pub fn new(name: &str, value: i32, sprite: &str) -> Self {
+        Treasure {
+            name: name.to_string(),
+            value: value,
+            sprite: sprite.to_string(),
+        }
+    }
+
+    pub fn defaults() -> Vec<Treasure> {
+        // novcici koji se nasumicno rasporedjuju po sobama
+        vec![Treasure::new("gold1", 5, "/images/gold1.png"),
+             Treasure::new("gold2", 15, "/images/gold2.png"),
+             Treasure::new("gold3", 50, "/images/gold3.png")]
     }
 }
 
+const GOLD_SIZE: f32 = 16.0; // slike zlata su 16x16 piksela
+
 pub struct Gold {
-    pos: mint::Point2<f32>,
-    image: graphics::Image,
+    pub pos: mint::Point2<f32>,
+    pub sprite: String, // koja slika se crta, iz Treasure
     value: i32,
     handle: CollisionObjectHandle,
 }
 impl Gold {
-    pub fn new (ctx: &mut Context, point1: mint::Point2<f32>, point2: mint::Point2<f32>, handle1: CollisionObjectHandle) -> Self {
-// handle ce nam kasnije pomoci da odredimo da li je igrac dodirnuo zlato (pokupio zlato)
-        let mut rng = thread_rng();
-        let num : i32 = rng.gen_range (1,4);
-
-
+    pub fn new (pos: mint::Point2<f32>, treasure: &Treasure, value: i32, handle1: CollisionObjectHandle) -> Self {
+        // handle ce nam kasnije pomoci da odredimo da li je igrac dodirnuo zlato (pokupio zlato)
         Gold {
-            pos: mint::Point2 { x: rng.gen_range(point1.x, point2.x),
-            y: rng.gen_range(point1.y, point2.y) },
-            image: graphics::Image::new(ctx, format!("/images/gold{}.png", num)).unwrap(),
-            value: match num {
-                1 => 5,
-                2 => 15,
-                3 => 50,
-                _ => 0,
-            },
+            pos: pos,
+            sprite: treasure.sprite.clone(),
+            value: value,
             handle: handle1,
         }
     }
-    pub fn update(&mut self, world: &mut CollisionWorld<f32, ()>, player_handle: CollisionObjectHandle, map_vel: Vector2<f32>) -> i32 {
-        self.pos.x += map_vel.x;
-        self.pos.y += map_vel.y;
+
+    pub fn random (floor: &[TilePos], grid: &Grid, map_start: mint::Point2<f32>, handle1: CollisionObjectHandle, rng: &mut StdRng) -> Self {
+        // nasumican novcic u sobi: svako polje poda sobe je jednako verovatno, a novcic je ceo unutar polja.
+        // Map::load proverava da soba sa zlatom ima bar jedno polje poda
+        let num : i32 = rng.gen_range (1,4);
+        let treasure = &Treasure::defaults()[(num - 1) as usize];
+        let tile = floor[rng.gen_range(0, floor.len())];
+        let corner = grid.point_of(tile, map_start);
+        let size = grid.tile_size();
+        let pos = mint::Point2 { x: corner.x + rng.gen_range(0.0, size.x - GOLD_SIZE),
+            y: corner.y + rng.gen_range(0.0, size.y - GOLD_SIZE) };
+        Gold::new(pos, treasure, treasure.value, handle1)
+    }
+
+    pub fn place(&self, world: &mut CollisionWorld<f32, ()>) {
+        // isto kao Door::place
         world.set_position(self.handle, Isometry2::new(Vector2::new(self.pos.x, self.pos.y), 0.0));
+    }
+
+    pub fn update(&mut self, world: &mut CollisionWorld<f32, ()>, player_handle: CollisionObjectHandle) -> i32 {
         match world.contact_pair(self.handle, player_handle, true) {
             // contact_pair vraca uredjenu cetvorku koja opisuje da li se desio sudar
             None => 0,
@@ -121,243 +170,502 @@ impl Gold {
             },
         }
     }
-    pub fn draw (&self, ctx: &mut Context) -> GameResult<()> {
-        graphics::draw(ctx, &self.image, graphics::DrawParam::new().dest(self.pos))?;
+}
+
+// Kamencic koji je igrac bacio. Leti pravo dok ne udari u zid ili ne padne,
+// a kad padne pravi zvuk koji moze da odvuce strazare
+#[derive(Debug, Clone)]
+pub struct Pebble {
+    pub pos: mint::Point2<f32>,
+    pub prev_pos: mint::Point2<f32>, // za crtanje izmedju dva ticka, kao kod igraca
+    dir: Vector2<f32>,
+    ticks_left: i32,
+}
+impl Pebble {
+    pub fn new(pos: mint::Point2<f32>, dir: Vector2<f32>) -> Self {
+        Pebble {
+            pos: pos,
+            prev_pos: pos,
+            dir: dir.normalize(),
+            ticks_left: PEBBLE_TICKS,
+        }
+    }
+
+    pub fn update(&mut self, world: &CollisionWorld<f32, ()>, map_handle: CollisionObjectHandle) -> bool {
+        // vraca true kad je kamencic pao. Zrak do sledece pozicije se sece sa mapom kao vidno polje strazara,
+        // pa kamencic ne moze da proleti kroz zid
+        self.prev_pos = self.pos;
+        let map_object = world.collision_object(map_handle).unwrap();
+        let map_shape: &Compound<f32> = map_object.shape().as_shape().unwrap();
+        let ray = Ray::new(Point2::new(self.pos.x, self.pos.y), self.dir);
+        match map_shape.toi_with_ray(map_object.position(), &ray, true) {
+            Some(toi) if toi < PEBBLE_SPD => {
+                // odbija se od zida i pada tik ispred njega
+                let end = ray.point_at((toi - 2.0).max(0.0));
+                self.pos = mint::Point2 { x: end.x, y: end.y };
+                true
+            },
+            _ => {
+                self.pos = mint::Point2 { x: self.pos.x + self.dir.x*PEBBLE_SPD, y: self.pos.y + self.dir.y*PEBBLE_SPD };
+                self.ticks_left -= 1;
+                self.ticks_left <= 0
+            },
+        }
+    }
+}
+
+fn check_range(file_name: &str, line_no: usize, column: usize, value: f32, min: f32, max: f32, what: &str) -> Result<(), LevelError> {
+    // vrednost iz zaglavlja mora biti ceo broj izmedju min i max (ukljucujuci oba), NaN ne prolazi
+    if value >= min && value <= max && value.fract() == 0.0 {
         Ok(())
+    } else {
+        Err(LevelError::new(file_name, line_no, column,
+                            format!("{}: expected a whole number from {} to {}, found {}", what, min, max, value)))
+    }
+}
+
+fn parse_route(file_name: &str, line_no: usize, route_no: i32, fields: &[(usize, &str)], startpos: mint::Point2<f32>, tile_size: mint::Point2<f32>, grid: &Grid) -> Result<(Vec<Waypoint>, RouteMode), LevelError> {
+    // guard <loop|pingpong> x,y[:cekanje[:pravac]] x,y[:cekanje[:pravac]] ...
+    // x,y je polje mape, cekanje je u sekundama, pravac je up, down, left ili right
+    let what = format!("guard line {}", route_no);
+    if fields.len() < 2 {
+        return Err(LevelError::new(file_name, line_no, 1,
+                                   format!("{}: expected a route mode and at least one waypoint", what)));
+    }
+    let mode = match fields[0].1 {
+        "loop" => RouteMode::Loop,
+        "pingpong" => RouteMode::PingPong,
+        m => return Err(LevelError::new(file_name, line_no, fields[0].0,
+                                        format!("{}: unknown route mode '{}', expected loop or pingpong", what, m))),
+    };
+    let mut route: Vec<Waypoint> = Vec::new();
+    for &(col, field) in fields[1..].iter() {
+        let parts = split_fields(field, ':');
+        let coor_vec = split_fields(parts[0].1, ',');
+        if parts.len() > 3 || coor_vec.len() != 2 {
+            return Err(LevelError::new(file_name, line_no, col,
+                                       format!("{}: expected a waypoint as x,y[:wait[:facing]], found '{}'", what, field)));
+        }
+        let x: usize = parse_field(file_name, line_no, col+coor_vec[0].0-1, coor_vec[0].1, &format!("{}: waypoint x", what))?;
+        let y: usize = parse_field(file_name, line_no, col+coor_vec[1].0-1, coor_vec[1].1, &format!("{}: waypoint y", what))?;
+        if !grid.is_walkable((x, y)) {
+            return Err(LevelError::new(file_name, line_no, col,
+                                       format!("{}: waypoint {},{} is not on a floor tile", what, x, y)));
+        }
+        let wait = if parts.len() > 1 {
+            let seconds: f32 = parse_field(file_name, line_no, col+parts[1].0-1, parts[1].1, &format!("{}: wait", what))?;
+            (seconds*TICKS_PER_SECOND) as i32
+        } else {
+            0
+        };
+        let facing = if parts.len() > 2 {
+            match parts[2].1 {
+                "up" => Some(Vector2::new(0.0, -1.0)),
+                "down" => Some(Vector2::new(0.0, 1.0)),
+                "left" => Some(Vector2::new(-1.0, 0.0)),
+                "right" => Some(Vector2::new(1.0, 0.0)),
+                f => return Err(LevelError::new(file_name, line_no, col+parts[2].0-1,
+                                                format!("{}: unknown facing '{}', expected up, down, left or right", what, f))),
+            }
+        } else {
+            None
+        };
+        route.push(Waypoint {
+            pos: mint::Point2 { x: startpos.x + x as f32*tile_size.x, y: startpos.y + y as f32*tile_size.y },
+            wait: wait,
+            facing: facing,
+        });
+    }
+    Ok((route, mode))
+}
+
+fn parse_treasure(file_name: &str, line_no: usize, fields: &[(usize, &str)], treasures: &Vec<Treasure>) -> Result<Treasure, LevelError> {
+    // treasure ime vrednost slika
+    if fields.len() != 3 {
+        return Err(LevelError::new(file_name, line_no, 1,
+                                   format!("treasure line: expected a name, a value and a sprite, found {} fields", fields.len())));
+    }
+    let name = fields[0].1;
+    if treasures.iter().any(|t| t.name == name) {
+        return Err(LevelError::new(file_name, line_no, fields[0].0,
+                                   format!("treasure line: treasure '{}' is already defined", name)));
+    }
+    let value: i32 = parse_field(file_name, line_no, fields[1].0, fields[1].1, &format!("treasure '{}': value", name))?;
+    if value <= 0 {
+        // Gold::update vraca 0 kad zlato nije pokupljeno
+        return Err(LevelError::new(file_name, line_no, fields[1].0,
+                                   format!("treasure '{}': value must be positive", name)));
+    }
+    if !fields[2].1.starts_with('/') {
+        return Err(LevelError::new(file_name, line_no, fields[2].0,
+                                   format!("treasure '{}': sprite must be a resource path starting with '/', found '{}'", name, fields[2].1)));
+    }
+    Ok(Treasure::new(name, value, fields[2].1))
+}
+
+fn parse_item<'a>(file_name: &str, line_no: usize, item_no: i32, fields: &[(usize, &str)], treasures: &'a Vec<Treasure>, grid: &Grid) -> Result<(usize, usize, &'a Treasure, i32), LevelError> {
+    // item x,y ime [vrednost] - blago na tacno odredjenom polju
+    let what = format!("item line {}", item_no);
+    if fields.len() != 2 && fields.len() != 3 {
+        return Err(LevelError::new(file_name, line_no, 1,
+                                   format!("{}: expected x,y, a treasure name and an optional value, found {} fields", what, fields.len())));
+    }
+    let (col, field) = fields[0];
+    let coor_vec = split_fields(field, ',');
+    if coor_vec.len() != 2 {
+        return Err(LevelError::new(file_name, line_no, col,
+                                   format!("{}: expected a tile as x,y, found '{}'", what, field)));
+    }
+    let x: usize = parse_field(file_name, line_no, col+coor_vec[0].0-1, coor_vec[0].1, &format!("{}: tile x", what))?;
+    let y: usize = parse_field(file_name, line_no, col+coor_vec[1].0-1, coor_vec[1].1, &format!("{}: tile y", what))?;
+    if !grid.is_walkable((x, y)) {
+        return Err(LevelError::new(file_name, line_no, col,
+                                   format!("{}: tile {},{} is not on a floor tile", what, x, y)));
+    }
+    let treasure = match treasures.iter().find(|t| t.name == fields[1].1) {
+        Some(t) => t,
+        None => return Err(LevelError::new(file_name, line_no, fields[1].0,
+                                           format!("{}: unknown treasure '{}'", what, fields[1].1))),
+    };
+    let value = if fields.len() == 3 {
+        parse_field(file_name, line_no, fields[2].0, fields[2].1, &format!("{}: value", what))?
+    } else {
+        treasure.value
+    };
+    if value <= 0 {
+        return Err(LevelError::new(file_name, line_no, fields[fields.len()-1].0,
+                                   format!("{}: value must be positive", what)));
     }
+    Ok((x, y, treasure, value))
 }
 
 pub struct Map {
     map_size: mint::Point2<f32>,
-    map_start: mint::Point2<f32>,
-    pub map_vel: Vector2<f32>,
-    map_spd: f32,
+    pub map_start: mint::Point2<f32>, // gornji levi ugao mape u koordinatama sveta, ne menja se
     map_tile_size: mint::Point2<f32>,
     map_corners: Vec<mint::Point2<f32>>,
-    map_matrix: Vec<Vec<Tile>>,
-    map_spritebatch: graphics::spritebatch::SpriteBatch,
+    pub map_matrix: Vec<Vec<Tile>>,
+    pub map_grid: Grid, // prohodnost polja, po njoj strazari traze put
     pub map_handle: CollisionObjectHandle,
-    map_guards: Vec<Guard>,
-    map_gold: Vec<Gold>,
+    pub map_guards: Vec<Guard>,
+    pub map_gold: Vec<Gold>,
+    pub map_gold_total: i32, // vrednost svog zlata na pocetku nivoa
+    pub map_treasures: Vec<Treasure>, // sve vrste blaga u nivou, da bi renderer ucitao slike
     pub map_door: Door,
+    pub map_pebbles: Vec<Pebble>, // kamencici koji jos lete
+    pub map_noises: NoiseBus, // zvuci koje strazari jos nisu culi
 }
 
 impl Map {
-    pub fn load<P>(ctx: &mut Context, level_filename: P, image_filename: P, startpos: mint::Point2<f32>, tile_size: mint::Point2<f32>, world_mut: &mut CollisionWorld<f32, ()>) -> GameResult<Self>
-        where
-        P: AsRef<Path>,
-        {
-            let spritesheet = graphics::Image::new(ctx, image_filename)?;
-            let swidth: f32 = spritesheet.width() as f32;
-            let sheigth: f32 = spritesheet.height() as f32;
-            let tfrac: mint::Point2<f32> = mint::Point2 { x: tile_size.x/swidth, y: tile_size.y/sheigth };
-            let mut map_file = filesystem::open(ctx, level_filename)?;
-            let mut map_string: String = "".to_string();
-            map_file.read_to_string(&mut map_string)?;
-            let mut map_lines = map_string.lines();
-
-            // U prvoj liniji treba da bude 4 broja -
-            // map_width map_heigth door_x door_y
-            let first_line: Vec<&str> = map_lines.next().unwrap().split(' ').collect();
-            let map_width: f32 = first_line[0].parse().unwrap();
-            let map_heigth: f32 = first_line[1].parse().unwrap();
-            let door_x: f32 = first_line[2].parse().unwrap();
-            let door_y: f32 = first_line[3].parse().unwrap();
-
-            let mut matrix: Vec<Vec<Tile>> = Vec::with_capacity(map_heigth as usize);
-
-            let mut curr_row_vec: Vec<Tile> = Vec::with_capacity(map_width as usize);
-            let mut curr_x = 0.0;
-            let mut curr_y = 0.0;
-
-            let shape_full = ShapeHandle::new(Cuboid::new(Vector2::new(16.0, 16.0)));
-            let shape_quart = ShapeHandle::new(Cuboid::new(Vector2::new(16.0, 8.0)));
-            let query = GeometricQueryType::Contacts(0.0, 0.0); // definisemo sta znaci dodir igraca i zlata i igraca i zida
-            let mut col_groups = CollisionGroups::new();
-            col_groups.set_membership(&[1 as usize]); // kojim grupama pripada objekat
-            col_groups.set_blacklist(&[1 as usize]); // sa kojim grupama ne moze da interaguje objekat
-            col_groups.set_whitelist(&[0 as usize]); // sa kojim grupama objekat moze da interaguje
-            let mut compound_shape_vec: Vec<(Isometry2<f32>, ShapeHandle<f32>)> = Vec::new();
-            let mut corner_points: Vec<mint::Point2<f32>> = Vec::new();
-
-            while curr_y < map_heigth-1.0 {
-                let line = map_lines.next().unwrap();
-                for c in line.chars() {
-                    match c {
-                        // treba pratiti x i y poziciju svakog polja, i na osnovu karaktera sa te
-                        // pozicije dodati polje odgovarajuceg tipa u matricu mape
-                        '1' => {
-                            corner_points.push(mint::Point2 { x: curr_x+12.0/32.0, y: curr_y+12.0/32.0});
-                            curr_row_vec.push(Tile::new(TileType::Wall([0.0, 0.0, tfrac.x, tfrac.y].into(), 2),
-                                                        mint::Point2 { x:curr_x, y:curr_y },
-                                                        tile_size))
-                        },
-                        '2' => curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x, 0.0, tfrac.x, tfrac.y].into(), 1),
-                                                           mint::Point2 { x:curr_x, y:curr_y },
-                                                           tile_size)),
-                        '3' => {
-                            corner_points.push(mint::Point2 { x: curr_x+20.0/32.0, y: curr_y+12.0/32.0 });
-                            curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*2.0, 0.0, tfrac.x, tfrac.y].into(), 2),
-                                                        mint::Point2 { x:curr_x, y:curr_y },
-                                                        tile_size))
-                        },
-                        '4' => {
-                            corner_points.push(mint::Point2 { x: curr_x+1.0, y: curr_y+12.0/32.0 });
-                            curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*3.0, 0.0, tfrac.x, tfrac.y].into(), 1),
-                                                        mint::Point2 { x:curr_x, y:curr_y },
-                                                        tile_size))
-                        },
-                        '5' => {
-                            corner_points.push(mint::Point2 { x: curr_x, y: curr_y+12.0/32.0 });
-                            curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*4.0, 0.0, tfrac.x, tfrac.y].into(), 1),
-                                                        mint::Point2 { x:curr_x, y:curr_y },
-                                                        tile_size))
-                        },
-                        '6' => curr_row_vec.push(Tile::new(TileType::Wall([0.0, tfrac.y*1.0, tfrac.x, tfrac.y].into(), 1),
-                                                           mint::Point2 { x:curr_x, y:curr_y },
-                                                           tile_size)),
-                        '7' => curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x, tfrac.y, tfrac.x, tfrac.y].into(), 1),
-                                                           mint::Point2 { x:curr_x, y:curr_y },
-                                                           tile_size)),
-                        '8' => curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*2.0, tfrac.y, tfrac.x, tfrac.y].into(), 1),
-                                                           mint::Point2 { x:curr_x, y:curr_y },
-                                                           tile_size)),
-                        '9' => {
-                            corner_points.push(mint::Point2 { x: curr_x+1.0, y: curr_y });
-                            curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*3.0, tfrac.y, tfrac.x, tfrac.y].into(), 1),
-                                                        mint::Point2 { x:curr_x, y:curr_y },
-                                                        tile_size))
-                        },
-                        'A' => {
-                            corner_points.push(mint::Point2 { x: curr_x, y: curr_y });
-                            curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*4.0, tfrac.y, tfrac.x, tfrac.y].into(), 1),
-                                                        mint::Point2 { x:curr_x, y:curr_y },
-                                                        tile_size))
-                        },
-                        'B' => {
-                            corner_points.push(mint::Point2 { x: curr_x+12.0/32.0, y: curr_y });
-                            curr_row_vec.push(Tile::new(TileType::Wall([0.0, tfrac.y*2.0, tfrac.x, tfrac.y].into(), 1),
-                                                        mint::Point2 { x:curr_x, y:curr_y },
-                                                        tile_size))
-                        },
-                        'C' => curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x, tfrac.y*2.0, tfrac.x, tfrac.y].into(), 2),
-                                                           mint::Point2 { x:curr_x, y:curr_y },
-                                                           tile_size)),
-                        'D' => {
-                            corner_points.push(mint::Point2 { x: curr_x+20.0/32.0, y: curr_y });
-                            curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*2.0, tfrac.y*2.0, tfrac.x, tfrac.y].into(), 1),
-                                                        mint::Point2 { x:curr_x, y:curr_y },
-                                                        tile_size))
-                        },
-                        ' ' => curr_row_vec.push(Tile::new(TileType::Floor([tfrac.x*3.0, tfrac.y*2.0, tfrac.x, tfrac.y].into(), 1),
-                                                           mint::Point2 { x:curr_x, y:curr_y },
-                                                           tile_size)),
-                        _   => (),
-                    }
-                    match curr_row_vec[curr_x as usize].tile_type {
-                        TileType::Wall(_, l) => {
-                            if l != 2 {
-                                compound_shape_vec.push((Isometry2::new(Vector2::new(curr_x*tile_size.x, curr_y*tile_size.y), 0.0), shape_full.clone()));
-                            } else {
-                                compound_shape_vec.push((Isometry2::new(Vector2::new(curr_x*tile_size.x, curr_y*tile_size.y+16.0), 0.0), shape_quart.clone()));
-                            }
-                            ()
-                        },
-                        _ => ()
-                    }
-                    curr_x += 1.0;
+    pub fn load(file_name: &str, map_string: &str, tfrac: mint::Point2<f32>, startpos: mint::Point2<f32>, tile_size: mint::Point2<f32>, world_mut: &mut CollisionWorld<f32, ()>, rng: &mut StdRng) -> GameResult<Self> {
+        // mapa se pravi od teksta nivoa, bez ggez konteksta, pa moze da se koristi i bez prozora.
+        // tfrac je velicina jednog polja kao deo slike sa spritesheetom, a rng je
+        // generator iz simulacije, pa isti seed uvek daje iste strazare i zlato
+        let mut map_lines = map_string.lines().enumerate().map(|(i, l)| (i+1, l)); // brojimo linije od 1
+
+        // U prvoj liniji treba da bude 4 broja -
+        // map_width map_heigth door_x door_y
+        let (first_no, first_text) = match map_lines.next() {
+            Some(l) => l,
+            None => return Err(LevelError::new(file_name, 1, 1, "empty level file".to_string()).into()),
+        };
+        let first_line = split_fields(first_text, ' ');
+        if first_line.len() != 4 {
+            return Err(LevelError::new(file_name, first_no, 1,
+                                       format!("header: expected 4 fields, found {}", first_line.len())).into());
+        }
+        let map_width: f32 = parse_field(file_name, first_no, first_line[0].0, first_line[0].1, "header: map width")?;
+        let map_heigth: f32 = parse_field(file_name, first_no, first_line[1].0, first_line[1].1, "header: map height")?;
+        let door_x: f32 = parse_field(file_name, first_no, first_line[2].0, first_line[2].1, "header: door x")?;
+        let door_y: f32 = parse_field(file_name, first_no, first_line[3].0, first_line[3].1, "header: door y")?;
+        // sirina i visina su za jedan vece od broja polja, a vrata moraju biti na nekom polju mape
+        check_range(file_name, first_no, first_line[0].0, map_width, 2.0, MAX_MAP_SIZE, "header: map width")?;
+        check_range(file_name, first_no, first_line[1].0, map_heigth, 2.0, MAX_MAP_SIZE, "header: map height")?;
+        check_range(file_name, first_no, first_line[2].0, door_x, 0.0, map_width - 2.0, "header: door x")?;
+        check_range(file_name, first_no, first_line[3].0, door_y, 0.0, map_heigth - 2.0, "header: door y")?;
+
+        let mut matrix: Vec<Vec<Tile>> = Vec::with_capacity(map_heigth as usize);
+
+        let mut curr_row_vec: Vec<Tile> = Vec::with_capacity(map_width as usize);
+        let mut curr_x = 0.0;
+        let mut curr_y = 0.0;
+
+        let shape_full = ShapeHandle::new(Cuboid::new(Vector2::new(16.0, 16.0)));
+        let shape_quart = ShapeHandle::new(Cuboid::new(Vector2::new(16.0, 8.0)));
+        let query = GeometricQueryType::Contacts(0.0, 0.0); // definisemo sta znaci dodir igraca i zlata i igraca i zida
+        let mut col_groups = CollisionGroups::new();
+        col_groups.set_membership(&[1 as usize]); // kojim grupama pripada objekat
+        col_groups.set_blacklist(&[1 as usize]); // sa kojim grupama ne moze da interaguje objekat
+        col_groups.set_whitelist(&[0 as usize]); // sa kojim grupama objekat moze da interaguje
+        let mut compound_shape_vec: Vec<(Isometry2<f32>, ShapeHandle<f32>)> = Vec::new();
+        let mut corner_points: Vec<mint::Point2<f32>> = Vec::new();
+
+        let mut last_line_no = first_no;
+        while curr_y < map_heigth-1.0 {
+            let (line_no, line) = match map_lines.next() {
+                Some(l) => l,
+                None => return Err(LevelError::new(file_name, last_line_no+1, 1,
+                                                   format!("map row {}: unexpected end of file", curr_y as i32 + 1)).into()),
+            };
+            last_line_no = line_no;
+            for c in line.chars() {
+                match c {
+                    // treba pratiti x i y poziciju svakog polja, i na osnovu karaktera sa te
+                    // pozicije dodati polje odgovarajuceg tipa u matricu mape
+                    '1' => {
+                        corner_points.push(mint::Point2 { x: curr_x+12.0/32.0, y: curr_y+12.0/32.0});
+                        curr_row_vec.push(Tile::new(TileType::Wall([0.0, 0.0, tfrac.x, tfrac.y].into(), 2),
+                                                    mint::Point2 { x:curr_x, y:curr_y },
+                                                    tile_size))
+                    },
+                    '2' => curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x, 0.0, tfrac.x, tfrac.y].into(), 1),
+                                                       mint::Point2 { x:curr_x, y:curr_y },
+                                                       tile_size)),
+                    '3' => {
+                        corner_points.push(mint::Point2 { x: curr_x+20.0/32.0, y: curr_y+12.0/32.0 });
+                        curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*2.0, 0.0, tfrac.x, tfrac.y].into(), 2),
+                                                    mint::Point2 { x:curr_x, y:curr_y },
+                                                    tile_size))
+                    },
+                    '4' => {
+                        corner_points.push(mint::Point2 { x: curr_x+1.0, y: curr_y+12.0/32.0 });
+                        curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*3.0, 0.0, tfrac.x, tfrac.y].into(), 1),
+                                                    mint::Point2 { x:curr_x, y:curr_y },
+                                                    tile_size))
+                    },
+                    '5' => {
+                        corner_points.push(mint::Point2 { x: curr_x, y: curr_y+12.0/32.0 });
+                        curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*4.0, 0.0, tfrac.x, tfrac.y].into(), 1),
+                                                    mint::Point2 { x:curr_x, y:curr_y },
+                                                    tile_size))
+                    },
+                    '6' => curr_row_vec.push(Tile::new(TileType::Wall([0.0, tfrac.y*1.0, tfrac.x, tfrac.y].into(), 1),
+                                                       mint::Point2 { x:curr_x, y:curr_y },
+                                                       tile_size)),
+                    '7' => curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x, tfrac.y, tfrac.x, tfrac.y].into(), 1),
+                                                       mint::Point2 { x:curr_x, y:curr_y },
+                                                       tile_size)),
+                    '8' => curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*2.0, tfrac.y, tfrac.x, tfrac.y].into(), 1),
+                                                       mint::Point2 { x:curr_x, y:curr_y },
+                                                       tile_size)),
+                    '9' => {
+                        corner_points.push(mint::Point2 { x: curr_x+1.0, y: curr_y });
+                        curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*3.0, tfrac.y, tfrac.x, tfrac.y].into(), 1),
+                                                    mint::Point2 { x:curr_x, y:curr_y },
+                                                    tile_size))
+                    },
+                    'A' => {
+                        corner_points.push(mint::Point2 { x: curr_x, y: curr_y });
+                        curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*4.0, tfrac.y, tfrac.x, tfrac.y].into(), 1),
+                                                    mint::Point2 { x:curr_x, y:curr_y },
+                                                    tile_size))
+                    },
+                    'B' => {
+                        corner_points.push(mint::Point2 { x: curr_x+12.0/32.0, y: curr_y });
+                        curr_row_vec.push(Tile::new(TileType::Wall([0.0, tfrac.y*2.0, tfrac.x, tfrac.y].into(), 1),
+                                                    mint::Point2 { x:curr_x, y:curr_y },
+                                                    tile_size))
+                    },
+                    'C' => curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x, tfrac.y*2.0, tfrac.x, tfrac.y].into(), 2),
+                                                       mint::Point2 { x:curr_x, y:curr_y },
+                                                       tile_size)),
+                    'D' => {
+                        corner_points.push(mint::Point2 { x: curr_x+20.0/32.0, y: curr_y });
+                        curr_row_vec.push(Tile::new(TileType::Wall([tfrac.x*2.0, tfrac.y*2.0, tfrac.x, tfrac.y].into(), 1),
+                                                    mint::Point2 { x:curr_x, y:curr_y },
+                                                    tile_size))
+                    },
+                    ' ' => curr_row_vec.push(Tile::new(TileType::Floor([tfrac.x*3.0, tfrac.y*2.0, tfrac.x, tfrac.y].into(), 1),
+                                                       mint::Point2 { x:curr_x, y:curr_y },
+                                                       tile_size)),
+                    _   => return Err(LevelError::new(file_name, line_no, curr_x as usize + 1,
+                                                      format!("unknown tile glyph '{}'", c)).into()),
+                }
+                match curr_row_vec[curr_x as usize].tile_type {
+                    TileType::Wall(_, l) => {
+                        if l != 2 {
+                            compound_shape_vec.push((Isometry2::new(Vector2::new(curr_x*tile_size.x, curr_y*tile_size.y), 0.0), shape_full.clone()));
+                        } else {
+                            compound_shape_vec.push((Isometry2::new(Vector2::new(curr_x*tile_size.x, curr_y*tile_size.y+16.0), 0.0), shape_quart.clone()));
+                        }
+                        ()
+                    },
+                    _ => ()
                 }
-                matrix.push(curr_row_vec.clone());
-                curr_row_vec.clear();
-                curr_x = 0.0;
-                curr_y += 1.0;
+                curr_x += 1.0;
             }
-            let mut guards_vec: Vec<Guard> = Vec::new();
-            let mut gold_vec: Vec<Gold> = Vec::new();
+            // kao i kod visine, sirina iz zaglavlja je za jedan veca od broja polja u redu
+            if curr_x != map_width-1.0 {
+                return Err(LevelError::new(file_name, line_no, curr_x as usize + 1,
+                                           format!("map row {}: expected {} tiles, found {}", curr_y as i32 + 1, map_width as i32 - 1, curr_x as i32)).into());
+            }
+            matrix.push(curr_row_vec.clone());
+            curr_row_vec.clear();
+            curr_x = 0.0;
+            curr_y += 1.0;
+        }
+        let mut guards_vec: Vec<Guard> = Vec::new();
+        let mut gold_vec: Vec<Gold> = Vec::new();
 
-            let shape_gold = ShapeHandle::new(Cuboid::new(Vector2::new(8.0, 8.0))); // "okvir" zlata, njegove granice da bismo mogli da definisemo "sudar" igraca i zlata
+        let shape_gold = ShapeHandle::new(Cuboid::new(Vector2::new(8.0, 8.0))); // "okvir" zlata, njegove granice da bismo mogli da definisemo "sudar" igraca i zlata
 
 
-            let first_dir = Vector2::new(0.0, 1.0);
-            let vec1 = Isometry2::new(Vector2::new(0.0,0.0), PI/6.0).transform_vector(&first_dir)*64.0;
-            let vec2 = Isometry2::new(Vector2::new(0.0,0.0), -PI/6.0).transform_vector(&first_dir)*64.0;
-            let origin_point = Point2::new(0.0, 0.0);
-            let triangle_points: [Point2<f32>; 3] = [origin_point, origin_point+vec1, origin_point+vec2]; 
+        let first_dir = Vector2::new(0.0, 1.0);
+        let vec1 = Isometry2::new(Vector2::new(0.0,0.0), PI/6.0).transform_vector(&first_dir)*64.0;
+        let vec2 = Isometry2::new(Vector2::new(0.0,0.0), -PI/6.0).transform_vector(&first_dir)*64.0;
+        let origin_point = Point2::new(0.0, 0.0);
+        let triangle_points: [Point2<f32>; 3] = [origin_point, origin_point+vec1, origin_point+vec2]; 
 
-            let shape_triangle = ShapeHandle::new(ConvexPolygon::try_from_points(&triangle_points).unwrap());
+        let shape_triangle = ShapeHandle::new(ConvexPolygon::try_from_points(&triangle_points).unwrap());
 
 
-            while let Some(guard_line) = map_lines.next() {
-                // posle opisa mape u txt fajlu sledi nekoliko redova koji
-                // imaju informacije o koordinatama soba, broju strazara i broju
-                // novcica
-                let split_space: Vec<&str> = guard_line.split(' ').collect();
+        let grid = Grid::new(&matrix, tile_size);
+        let mut treasures = Treasure::defaults();
+        let mut room_no = 0;
+        let mut route_no = 0;
+        let mut item_no = 0;
+        while let Some((line_no, guard_line)) = map_lines.next() {
+            // posle opisa mape u txt fajlu sledi nekoliko redova koji
+            // imaju informacije o koordinatama soba, broju strazara i broju
+            // novcica, ili rute strazara koje pocinju sa "guard", ili
+            // vrste blaga ("treasure") i blago na tacnom polju ("item")
+            if guard_line.trim().is_empty() {
+                continue;
+            }
+            let split_space = split_fields(guard_line, ' ');
+            if split_space[0].1 == "guard" {
+                route_no += 1;
+                let (route, mode) = parse_route(file_name, line_no, route_no, &split_space[1..], startpos, tile_size, &grid)?;
+                guards_vec.push(Guard::new(route, mode,
+                    world_mut.add(Isometry2::new(Vector2::new(startpos.x, startpos.y), 0.0),
+                     shape_triangle.clone(),
+                     col_groups,
+                     query,
+                     ()).handle()));
+                continue;
+            }
+            if split_space[0].1 == "treasure" {
+                let treasure = parse_treasure(file_name, line_no, &split_space[1..], &treasures)?;
+                treasures.push(treasure);
+                continue;
+            }
+            if split_space[0].1 == "item" {
+                item_no += 1;
+                let (x, y, treasure, value) = parse_item(file_name, line_no, item_no, &split_space[1..], &treasures, &grid)?;
+                // slika je centrirana na polju
+                let pos = mint::Point2 { x: startpos.x + x as f32*tile_size.x + (tile_size.x - GOLD_SIZE)/2.0,
+                    y: startpos.y + y as f32*tile_size.y + (tile_size.y - GOLD_SIZE)/2.0 };
+                gold_vec.push(Gold::new(pos, treasure, value,
+                    world_mut.add(Isometry2::new(Vector2::new(startpos.x, startpos.y), 0.0),
+                     shape_gold.clone(),
+                     col_groups,
+                     query,
+                     ()).handle()));
+                continue;
+            }
+            room_no += 1;
+            if split_space.len() != 5 {
+                return Err(LevelError::new(file_name, line_no, 1,
+                                           format!("room line {}: expected 5 fields, found {}", room_no, split_space.len())).into());
+            }
 
-                let point1_vec: Vec<&str> = split_space[0].split(',').collect();
-                let point1_x: f32 = point1_vec[0].parse().unwrap();
-                let point1_y: f32 = point1_vec[1].parse().unwrap();
+            let mut corners: Vec<(f32, f32)> = Vec::new();
+            for &(col, field) in split_space[0..2].iter() {
+                let coor_vec = split_fields(field, ',');
+                if coor_vec.len() != 2 {
+                    return Err(LevelError::new(file_name, line_no, col,
+                                               format!("room line {}: expected a corner as x,y, found '{}'", room_no, field)).into());
+                }
+                let x: f32 = parse_field(file_name, line_no, col+coor_vec[0].0-1, coor_vec[0].1, &format!("room line {}: corner x", room_no))?;
+                let y: f32 = parse_field(file_name, line_no, col+coor_vec[1].0-1, coor_vec[1].1, &format!("room line {}: corner y", room_no))?;
+                corners.push((x, y));
+            }
+            let (point1_x, point1_y) = corners[0];
+            let (point2_x, point2_y) = corners[1];
+            if point1_x >= point2_x || point1_y >= point2_y {
+                // gen_range bi pao ako soba nema povrsinu
+                return Err(LevelError::new(file_name, line_no, split_space[1].0,
+                                           format!("room line {}: second corner must be below and right of the first", room_no)).into());
+            }
 
-                let point1: mint::Point2<f32> = mint::Point2 { x: startpos.x + point1_x*tile_size.x, y: startpos.y + point1_y*tile_size.y };
+            let point1: mint::Point2<f32> = mint::Point2 { x: startpos.x + point1_x*tile_size.x, y: startpos.y + point1_y*tile_size.y };
+            let point2: mint::Point2<f32> = mint::Point2 { x: startpos.x + point2_x*tile_size.x, y: startpos.y + point2_y*tile_size.y };
 
-                let point2_vec: Vec<&str> = split_space[1].split(',').collect();
-                let point2_x: f32 = point2_vec[0].parse().unwrap();
-                let point2_y: f32 = point2_vec[1].parse().unwrap();
+            let number_of_guards: i32 = parse_field(file_name, line_no, split_space[2].0, split_space[2].1, &format!("room line {}: guards", room_no))?;
+            let number_of_points: i32 = parse_field(file_name, line_no, split_space[3].0, split_space[3].1, &format!("room line {}: patrol points", room_no))?;
+            let number_of_coins: i32 = parse_field(file_name, line_no, split_space[4].0, split_space[4].1, &format!("room line {}: coins", room_no))?;
+            if number_of_guards > 0 && number_of_points < 1 {
+                return Err(LevelError::new(file_name, line_no, split_space[3].0,
+                                           format!("room line {}: guards need at least one patrol point", room_no)).into());
+            }
 
-                let point2: mint::Point2<f32> = mint::Point2 { x: startpos.x + point2_x*tile_size.x, y: startpos.y + point2_y*tile_size.y };
+            for _i in 0..number_of_guards {
+                let route = Guard::random_route(point1, point2, number_of_points, rng);
+                guards_vec.push(Guard::new(route, RouteMode::Loop,
+                    world_mut.add(Isometry2::new(Vector2::new(startpos.x, startpos.y), 0.0), // world_mut je neophodan zbog sudaranja igraca i zlata
+                     shape_triangle.clone(),
+                     col_groups,
+                     query,
+                     ()).handle())); // do handle je poziv funkcije world_mut.add koja dodaje objekat u svet za koliziju (ne crta ga)
 
-                let number_of_guards: i32 = split_space[2].parse().unwrap();
-                let number_of_points: i32 = split_space[3].parse().unwrap();
-                let number_of_coins: i32 = split_space[4].parse().unwrap();
+            }
 
-                for _i in 0..number_of_guards {
-                    guards_vec.push(Guard::new(ctx, point1, point2, number_of_points,
-                        world_mut.add(Isometry2::new(Vector2::new(startpos.x, startpos.y), 0.0), // world_mut je neophodan zbog sudaranja igraca i zlata
-                         shape_triangle.clone(),
-                         col_groups,
-                         query,
-                         ()).handle())); // do handle je poziv funkcije world_mut.add koja dodaje objekat u svet za koliziju (ne crta ga)
+            let floor = grid.walkable_in((point1_x.max(0.0).floor() as usize, point1_y.max(0.0).floor() as usize),
+                                         (point2_x.max(0.0).ceil() as usize, point2_y.max(0.0).ceil() as usize));
+            if number_of_coins > 0 && floor.is_empty() {
+                return Err(LevelError::new(file_name, line_no, split_space[4].0,
+                                           format!("room line {}: coins need a floor tile inside the room", room_no)).into());
+            }
+            for _i in 0..number_of_coins { // pravimo vektor koji sadrzi svo zlato na mapi
+                gold_vec.push(Gold::random(&floor, &grid, startpos,
+                    world_mut.add(Isometry2::new(Vector2::new(startpos.x, startpos.y), 0.0), // world_mut je neophodan zbog sudaranja igraca i zlata
+                     shape_gold.clone(),
+                     col_groups,
+                     query,
+                     ()).handle(), rng)); // do handle je poziv funkcije world_mut.add koja dodaje objekat u svet za koliziju (ne crta ga)
+            }
 
-                }
+        }
 
-                for _i in 0..number_of_coins { // pravimo vektor koji sadrzi svo zlato na mapi
-                    gold_vec.push(Gold::new(ctx, point1, point2,
-                        world_mut.add(Isometry2::new(Vector2::new(startpos.x, startpos.y), 0.0), // world_mut je neophodan zbog sudaranja igraca i zlata
-                         shape_gold.clone(),
-                         col_groups,
-                         query,
-                         ()).handle())); // do handle je poziv funkcije world_mut.add koja dodaje objekat u svet za koliziju (ne crta ga)
-                }
+        let door = Door::new(mint::Point2 { x: startpos.x + door_x*tile_size.x, y: startpos.y + door_y*tile_size.y }, world_mut.add(Isometry2::new(Vector2::new(startpos.x, startpos.y), 0.0), shape_full.clone(), col_groups, query, ()).handle());
+        door.place(world_mut);
+        for gold in gold_vec.iter() {
+            gold.place(world_mut);
+        }
 
-            }
+        Ok(Map {
+            map_size: mint::Point2 { x: map_width, y: map_heigth }, // ovo je broj polja na mapi
+            map_start: startpos,
+            map_tile_size: tile_size,
+            map_corners: corner_points,
+            map_grid: grid,
+            map_matrix: matrix,
+            map_handle: world_mut.add(Isometry2::new(Vector2::new(startpos.x, startpos.y), 0.0), ShapeHandle::new(Compound::new(compound_shape_vec)), col_groups, query, ()).handle(),
+            map_guards: guards_vec,
+            map_gold_total: gold_vec.iter().map(|g| g.value).sum(),
+            map_gold: gold_vec,
+            map_treasures: treasures,
+            map_door: door,
+            map_pebbles: Vec::new(),
+            map_noises: NoiseBus::default(),
+        })
+    }
 
-            let door = Door::new(ctx, mint::Point2 { x: startpos.x + door_x*tile_size.x, y: startpos.y + door_y*tile_size.y }, world_mut.add(Isometry2::new(Vector2::new(startpos.x, startpos.y), 0.0), shape_full.clone(), col_groups, query, ()).handle());
-
-            Ok(Map {
-                map_size: mint::Point2 { x: map_width, y: map_heigth }, // ovo je broj polja na mapi
-                map_start: startpos,
-                map_vel: Vector2::new(0.0, 0.0),
-                map_spd: 4.0,
-                map_tile_size: tile_size,
-                map_corners: corner_points,
-                map_matrix: matrix,
-                map_spritebatch: graphics::spritebatch::SpriteBatch::new(spritesheet),
-                map_handle: world_mut.add(Isometry2::new(Vector2::new(startpos.x, startpos.y), 0.0), ShapeHandle::new(Compound::new(compound_shape_vec)), col_groups, query, ()).handle(),
-                map_guards: guards_vec,
-                map_gold: gold_vec,
-                map_door: door,
-            })
-        }
+    pub fn bounds(&self) -> graphics::Rect {
+        // cela mapa u koordinatama sveta, za kameru. Polja se broje u matrici, jer je map_size iz zaglavlja za jedan veci
+        let rows = self.map_matrix.len() as f32;
+        let columns = self.map_matrix.iter().map(|row| row.len()).max().unwrap_or(0) as f32;
+        graphics::Rect::new(self.map_start.x, self.map_start.y, columns*self.map_tile_size.x, rows*self.map_tile_size.y)
+    }
 
-    pub fn update(&mut self, world: &mut CollisionWorld<f32, ()>, dir: Vector2<f32>) {
-        let dir_norm = if dir.x != 0.0 || dir.y != 0.0 {
-            (-dir).normalize()
-        } else {
-            dir
-        };
-        self.map_vel = dir_norm*self.map_spd;
-        self.map_start.x += self.map_vel.x;
-        self.map_start.y += self.map_vel.y;
-        world.set_position(self.map_handle, Isometry2::new(Vector2::new(self.map_start.x, self.map_start.y), 0.0));
+    pub fn keep_gold(&mut self, world: &mut CollisionWorld<f32, ()>, offsets: &[mint::Point2<f32>]) {
+        // ostavlja samo zlato koje je na nekom od zadatih mesta (u odnosu na map_start), ostalo je vec pokupljeno
+        let map_start = self.map_start;
+        let matches = |gold: &Gold| offsets.iter().any(|o| (gold.pos.x - map_start.x - o.x).abs() < 0.5 && (gold.pos.y - map_start.y - o.y).abs() < 0.5);
+        for gold in self.map_gold.iter().filter(|g| !matches(g)) {
+            world.remove(&[gold.handle]);
+        }
+        self.map_gold.retain(|g| matches(g));
     }
 
     pub fn update_gold(&mut self, world: &mut CollisionWorld<f32, ()>, player_handle: CollisionObjectHandle) -> i32 {
@@ -366,7 +674,12 @@ impl Map {
         let mut duzina = self.map_gold.len();
         let mut i: usize = 0;
         while i < duzina {
-            let sudaren = self.map_gold[i].update(world, player_handle, self.map_vel);
+            let sudaren = self.map_gold[i].update(world, player_handle);
+            if sudaren != 0 {
+                // zveckanje novcica se cuje oko mesta gde je zlato bilo
+                let pos = self.map_gold[i].pos;
+                self.map_noises.publish(Noise { pos: mint::Point2 { x: pos.x + GOLD_SIZE/2.0, y: pos.y + GOLD_SIZE/2.0 }, radius: COINS_NOISE, kind: NoiseKind::Coins });
+            }
             match sudaren { //vraca 0 ako se igrac nije sudario sa zlatom,i neku vrednost ako jeste
                 0 => (),
                 vrednost => {
@@ -382,72 +695,114 @@ impl Map {
         zbir
     }
 
-    pub fn update_guards(&mut self, world: &mut CollisionWorld<f32, ()>, player_handle: CollisionObjectHandle) -> bool {
-        let mut res: bool = false;
+    pub fn throw_pebble(&mut self, pos: mint::Point2<f32>, dir: Vector2<f32>) {
+        self.map_pebbles.push(Pebble::new(pos, dir));
+    }
+
+    pub fn update_pebbles(&mut self, world: &CollisionWorld<f32, ()>) {
+        // kamencici koji su pali u ovom ticku prave zvuk i nestaju
+        let mut i: usize = 0;
+        while i < self.map_pebbles.len() {
+            if self.map_pebbles[i].update(world, self.map_handle) {
+                let pebble = self.map_pebbles.remove(i);
+                self.map_noises.publish(Noise { pos: pebble.pos, radius: PEBBLE_NOISE, kind: NoiseKind::Pebble });
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    pub fn update_guards(&mut self, world: &mut CollisionWorld<f32, ()>, player_handle: CollisionObjectHandle, rng: &mut StdRng) -> Alertness {
+        // vraca stanje najuzbunjenijeg strazara. Strazari cuju sve zvukove objavljene od
+        // njihovog prethodnog updatea, pa se oni posle brisu
+        let mut res = Alertness::Calm;
         for i in 0..self.map_guards.len() {
-            res = res || self.map_guards[i].update(world, player_handle, self.map_vel);
+            let alertness = self.map_guards[i].update(world, player_handle, self.map_handle, &self.map_grid, self.map_start, self.map_noises.noises(), rng);
+            if alertness > res {
+                res = alertness;
+            }
         }
+        self.map_noises.clear();
         res
     }
 
+    pub fn guards_evaded(&self) -> i32 {
+        // koliko puta su strazari odustali od potere ili sumnje
+        self.map_guards.iter().map(|g| g.gave_up).sum()
+    }
+
     pub fn get_corners(&mut self) -> Vec<mint::Point2<f32>> {
         self.map_corners.clone().into_iter().map(|c| mint::Point2 { x: self.map_start.x + c.x*self.map_tile_size.x,
             y: self.map_start.y + c.y*self.map_tile_size.y }).collect()
     }
+}
 
-    pub fn draw_gold(&mut self, ctx: &mut Context) -> GameResult<()> {
-        for gold in self.map_gold.iter() {
-            gold.draw(ctx)?;
-        }
-        Ok(())
-        // ova funkcija crta na ekran sve zlatnike tj pojedinacno poziva draw za svaki gold
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    // najmanja mapa: 4x3 polja, pod su samo dva polja u sredini
+    const SMALL: &str = "5 4 1 0\n2222\n2  2\n2222\n";
+
+    fn load(text: &str) -> GameResult<Map> {
+        let mut world = CollisionWorld::new(0.02);
+        let mut rng = StdRng::seed_from_u64(0);
+        Map::load("bad.txt", text, mint::Point2 { x: 0.2, y: 0.25 }, mint::Point2 { x: 100.0, y: 164.0 },
+                  mint::Point2 { x: TILE_SIZE, y: TILE_SIZE }, &mut world, &mut rng)
     }
 
-    pub fn draw_guards(&mut self, ctx: &mut Context) -> GameResult<()> {
-        for guard in self.map_guards.iter() {
-            guard.draw(ctx)?;
+    fn load_error(text: &str) -> String {
+        match load(text) {
+            Err(GameError::ResourceLoadError(msg)) => msg,
+            Err(e) => panic!("unexpected error {:?}", e),
+            Ok(_) => panic!("level loaded"),
         }
-        Ok(())
     }
 
-    pub fn draw_guard_vision(&mut self, ctx: &mut Context) -> GameResult<()> {
-        for guard in self.map_guards.iter() {
-            guard.draw_vision(ctx)?;
-        }
-        Ok(())
+    #[test]
+    fn loads_small_map() {
+        let map = load(SMALL).unwrap();
+        assert_eq!(map.map_matrix.len(), 3);
+        assert_eq!(map.map_matrix[0].len(), 4);
+        assert!(map.map_grid.is_walkable((1, 1)));
+        assert!(!map.map_grid.is_walkable((0, 1)));
+        assert_eq!(map.bounds(), graphics::Rect::new(100.0, 164.0, 4.0*TILE_SIZE, 3.0*TILE_SIZE));
+    }
 
+    #[test]
+    fn header_errors_point_at_the_field() {
+        assert_eq!(load_error(""), "bad.txt:1:1: empty level file");
+        assert_eq!(load_error("5 4 1\n"), "bad.txt:1:1: header: expected 4 fields, found 3");
+        assert!(load_error("5 x 1 0\n").starts_with("bad.txt:1:3: header: map height: expected a number"));
+        assert!(load_error("100000 4 1 0\n").starts_with("bad.txt:1:1: header: map width"));
+        assert!(load_error("5 -3 1 0\n").starts_with("bad.txt:1:3: header: map height"));
+        assert!(load_error("5 4 9 0\n").starts_with("bad.txt:1:5: header: door x"));
+        assert!(load_error("5 4 1 3\n").starts_with("bad.txt:1:7: header: door y"));
     }
 
-    pub fn draw(&mut self, ctx: &mut Context, layer: i32, show_mesh: bool) -> GameResult<()> {
-        for row in self.map_matrix.iter() {
-            for tile in row.iter() {
-                if tile.tile_layer == layer {
-                    self.map_spritebatch.add(tile.drawparam(self.map_start));
-                }
-            }
-        }
-        self.map_spritebatch.set_filter(graphics::FilterMode::Nearest);
-        graphics::draw(ctx, &self.map_spritebatch, graphics::DrawParam::new())?;
-        self.map_spritebatch.clear();
-
-        if show_mesh {
-            let mut tile_mesh = graphics::MeshBuilder::new();
-            for row in self.map_matrix.iter() {
-                for tile in row.iter() {
-                    if tile.tile_layer == layer {
-                        match tile.tile_type {
-                            TileType::Wall(_, _) => {
-                                tile_mesh.rectangle(graphics::DrawMode::stroke(3.0), [tile.drawparam(self.map_start).dest.x, tile.drawparam(self.map_start).dest.y, tile.tile_size.x, tile.tile_size.y].into(), [0.0, 1.0, 0.0, 1.0].into());
-                                ()
-                            },
-                            TileType::Floor(_, _) => (),
-                        }
-                    }
-                }
-            }
-            let built_mesh = tile_mesh.build(ctx)?;
-            graphics::draw(ctx, &built_mesh, graphics::DrawParam::new())?;
+    #[test]
+    fn map_row_errors_point_at_the_tile() {
+        assert_eq!(load_error("5 4 1 0\n2222\n2 X2\n2222\n"), "bad.txt:3:3: unknown tile glyph 'X'");
+        assert_eq!(load_error("5 4 1 0\n2222\n2 2\n2222\n"), "bad.txt:3:4: map row 2: expected 4 tiles, found 3");
+        assert_eq!(load_error("5 4 1 0\n2222\n"), "bad.txt:3:1: map row 2: unexpected end of file");
+    }
+
+    #[test]
+    fn waypoint_in_wall_is_rejected() {
+        let text = format!("{}guard loop 1,1 0,0\n", SMALL);
+        assert_eq!(load_error(&text), "bad.txt:5:16: guard line 1: waypoint 0,0 is not on a floor tile");
+    }
+
+    #[test]
+    fn room_gold_lands_on_floor() {
+        let map = load(&format!("{}0,0 4,3 0 0 6\n", SMALL)).unwrap();
+        assert_eq!(map.map_gold.len(), 6);
+        for gold in map.map_gold.iter() {
+            let tile = map.map_grid.tile_of(mint::Point2 { x: gold.pos.x + GOLD_SIZE/2.0, y: gold.pos.y + GOLD_SIZE/2.0 }, map.map_start);
+            assert!(tile.map_or(false, |t| map.map_grid.is_walkable(t)));
         }
-        Ok(())
+        let text = format!("{}0,0 1,1 0 0 2\n", SMALL);
+        assert_eq!(load_error(&text), "bad.txt:5:13: room line 1: coins need a floor tile inside the room");
     }
 }
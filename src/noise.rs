@@ -0,0 +1,46 @@
+use ggez::mint;
+
+// Odakle je zvuk, strazari reaguju isto na sve, ali je svaki drugacije glasan
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseKind {
+    Footsteps,
+    Coins, // pokupljeno zlato
+    Door,
+    Pebble, // kamencic koji je igrac bacio pao na pod
+}
+
+// Zvuk u jednom ticku: strazari cije su oci na manje od radius od pos ga cuju, ako ih zid ne zaklanja
+#[derive(Debug, Clone, Copy)]
+pub struct Noise {
+    pub pos: mint::Point2<f32>,
+    pub radius: f32,
+    pub kind: NoiseKind,
+}
+
+impl Noise {
+    pub fn distance(&self, ear: mint::Point2<f32>) -> f32 {
+        let (dx, dy) = (self.pos.x - ear.x, self.pos.y - ear.y);
+        (dx*dx + dy*dy).sqrt()
+    }
+}
+
+// Svi zvuci od poslednjeg updatea strazara. Izvori ih objavljuju kad god se nesto desi,
+// a Map::update_guards ih prosledi svim strazarima i isprazni
+#[derive(Debug, Clone, Default)]
+pub struct NoiseBus {
+    noises: Vec<Noise>,
+}
+
+impl NoiseBus {
+    pub fn publish(&mut self, noise: Noise) {
+        self.noises.push(noise);
+    }
+
+    pub fn noises(&self) -> &[Noise] {
+        &self.noises
+    }
+
+    pub fn clear(&mut self) {
+        self.noises.clear();
+    }
+}
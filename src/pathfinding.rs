@@ -0,0 +1,252 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+use ggez::mint;
+
+use crate::map::{Tile, TileType};
+
+pub type TilePos = (usize, usize); // (kolona, red) u matrici mape
+
+// Mreza prohodnosti napravljena od matrice mape - pod je prohodan, zid nije
+#[derive(Debug, Clone)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    walkable: Vec<bool>,
+    tile_size: mint::Point2<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Node {
+    cost: f32, // do sada predjen put + procena do cilja
+    tile: TilePos,
+}
+
+impl Eq for Node {}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Node) -> Ordering {
+        // BinaryHeap je max-heap, pa obrcemo poredjenje da bi na vrhu bio najjeftiniji cvor
+        other.cost.partial_cmp(&self.cost).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Node) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Grid {
+    pub fn new(matrix: &Vec<Vec<Tile>>, tile_size: mint::Point2<f32>) -> Self {
+        let height = matrix.len();
+        let width = matrix.iter().map(|row| row.len()).max().unwrap_or(0);
+        let mut walkable = vec![false; width*height];
+        for (y, row) in matrix.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                walkable[y*width + x] = match tile.tile_type {
+                    TileType::Floor(_, _) => true,
+                    TileType::Wall(_, _) => false,
+                };
+            }
+        }
+        Grid {
+            width: width,
+            height: height,
+            walkable: walkable,
+            tile_size: tile_size,
+        }
+    }
+
+    pub fn tile_size(&self) -> mint::Point2<f32> {
+        self.tile_size
+    }
+
+    pub fn is_walkable(&self, tile: TilePos) -> bool {
+        tile.0 < self.width && tile.1 < self.height && self.walkable[tile.1*self.width + tile.0]
+    }
+
+    pub fn tile_of(&self, point: mint::Point2<f32>, map_start: mint::Point2<f32>) -> Option<TilePos> {
+        // u kom polju se nalazi tacka sa ekrana, None ako je van mape
+        let x = ((point.x - map_start.x)/self.tile_size.x).floor();
+        let y = ((point.y - map_start.y)/self.tile_size.y).floor();
+        if x < 0.0 || y < 0.0 || x as usize >= self.width || y as usize >= self.height {
+            return None
+        }
+        Some((x as usize, y as usize))
+    }
+
+    pub fn point_of(&self, tile: TilePos, map_start: mint::Point2<f32>) -> mint::Point2<f32> {
+        // gornji levi ugao polja na ekranu, isto kao Tile::drawparam
+        mint::Point2 { x: map_start.x + tile.0 as f32*self.tile_size.x, y: map_start.y + tile.1 as f32*self.tile_size.y }
+    }
+
+    pub fn walkable_in(&self, from: TilePos, to: TilePos) -> Vec<TilePos> {
+        // prohodna polja u pravougaoniku od from do to (bez to), red po red
+        let mut tiles: Vec<TilePos> = Vec::new();
+        for y in from.1..to.1.min(self.height) {
+            for x in from.0..to.0.min(self.width) {
+                if self.is_walkable((x, y)) {
+                    tiles.push((x, y));
+                }
+            }
+        }
+        tiles
+    }
+
+    pub fn nearest_walkable(&self, tile: TilePos) -> Option<TilePos> {
+        // pretraga u sirinu od zadatog polja, za slucaj da je tacka zavrsila u zidu
+        let mut visited = vec![false; self.width*self.height];
+        let mut queue: VecDeque<TilePos> = VecDeque::new();
+        if tile.0 >= self.width || tile.1 >= self.height {
+            return None
+        }
+        visited[tile.1*self.width + tile.0] = true;
+        queue.push_back(tile);
+        while let Some(curr) = queue.pop_front() {
+            if self.is_walkable(curr) {
+                return Some(curr)
+            }
+            for &(next, _) in self.neighbours(curr, false).iter() {
+                if !visited[next.1*self.width + next.0] {
+                    visited[next.1*self.width + next.0] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    fn neighbours(&self, tile: TilePos, only_walkable: bool) -> Vec<(TilePos, f32)> {
+        // susedna polja sa cenom prelaska; dijagonalno se ide samo ako
+        // oba polja pored dijagonale nisu zid, da strazar ne bi secao ugao zida
+        let mut res: Vec<(TilePos, f32)> = Vec::new();
+        let (x, y) = (tile.0 as i32, tile.1 as i32);
+        for dy in -1..2 {
+            for dx in -1..2 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx as usize >= self.width || ny as usize >= self.height {
+                    continue;
+                }
+                let next = (nx as usize, ny as usize);
+                if only_walkable {
+                    if !self.is_walkable(next) {
+                        continue;
+                    }
+                    if dx != 0 && dy != 0 && (!self.is_walkable((nx as usize, y as usize)) || !self.is_walkable((x as usize, ny as usize))) {
+                        continue;
+                    }
+                }
+                let cost = if dx != 0 && dy != 0 { 1.414 } else { 1.0 };
+                res.push((next, cost));
+            }
+        }
+        res
+    }
+
+    fn heuristic(a: TilePos, b: TilePos) -> f32 {
+        // oktilno rastojanje, odgovara kretanju u 8 pravaca
+        let dx = (a.0 as f32 - b.0 as f32).abs();
+        let dy = (a.1 as f32 - b.1 as f32).abs();
+        dx.max(dy) + 0.414*dx.min(dy)
+    }
+
+    pub fn find_path(&self, start: TilePos, goal: TilePos) -> Option<Vec<TilePos>> {
+        // A* od start do goal. Vraca polja kojima treba proci, bez startnog a sa ciljnim.
+        // Startno polje sme da bude zid (strazar je mozda zagazio u zid), ciljno ne sme
+        if start.0 >= self.width || start.1 >= self.height || !self.is_walkable(goal) {
+            return None
+        }
+        let index = |t: TilePos| t.1*self.width + t.0;
+        let mut best_cost = vec![std::f32::MAX; self.width*self.height];
+        let mut came_from: Vec<Option<TilePos>> = vec![None; self.width*self.height];
+        let mut open = BinaryHeap::new();
+        best_cost[index(start)] = 0.0;
+        open.push(Node { cost: Grid::heuristic(start, goal), tile: start });
+
+        while let Some(Node { tile, .. }) = open.pop() {
+            if tile == goal {
+                let mut path: Vec<TilePos> = Vec::new();
+                let mut curr = goal;
+                while curr != start {
+                    path.push(curr);
+                    curr = came_from[index(curr)].unwrap();
+                }
+                path.reverse();
+                return Some(path)
+            }
+            for &(next, step) in self.neighbours(tile, true).iter() {
+                let cost = best_cost[index(tile)] + step;
+                if cost < best_cost[index(next)] {
+                    best_cost[index(next)] = cost;
+                    came_from[index(next)] = Some(tile);
+                    open.push(Node { cost: cost + Grid::heuristic(next, goal), tile: next });
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ggez::graphics;
+
+    fn grid(rows: &[&str]) -> Grid {
+        // '#' je zid, sve ostalo je pod
+        let size = mint::Point2 { x: 32.0, y: 32.0 };
+        let src = graphics::Rect::new(0.0, 0.0, 1.0, 1.0);
+        let matrix: Vec<Vec<Tile>> = rows.iter().enumerate().map(|(y, row)| row.chars().enumerate().map(|(x, c)| {
+            let tile_type = if c == '#' { TileType::Wall(src, 1) } else { TileType::Floor(src, 1) };
+            Tile::new(tile_type, mint::Point2 { x: x as f32, y: y as f32 }, size)
+        }).collect()).collect();
+        Grid::new(&matrix, size)
+    }
+
+    #[test]
+    fn straight_and_diagonal_paths() {
+        let open = grid(&["....", "....", "...."]);
+        assert_eq!(open.find_path((0, 0), (3, 0)), Some(vec![(1, 0), (2, 0), (3, 0)]));
+        assert_eq!(open.find_path((0, 0), (2, 2)), Some(vec![(1, 1), (2, 2)]));
+        assert_eq!(open.find_path((1, 1), (1, 1)), Some(vec![]));
+    }
+
+    #[test]
+    fn walks_around_walls_without_cutting_corners() {
+        let g = grid(&["...", ".#.", "..."]);
+        let path = g.find_path((0, 1), (2, 1)).unwrap();
+        assert_eq!(path.len(), 4); // dijagonala pored zida nije dozvoljena, pa ide preko ugla
+        assert_eq!(path.last(), Some(&(2, 1)));
+        assert!(path.iter().all(|t| g.is_walkable(*t)));
+    }
+
+    #[test]
+    fn no_path_to_wall_or_closed_room() {
+        let g = grid(&["..#..", "..#..", "..#.."]);
+        assert_eq!(g.find_path((0, 0), (2, 0)), None);
+        assert_eq!(g.find_path((0, 0), (4, 2)), None);
+        assert_eq!(g.find_path((9, 9), (0, 0)), None);
+    }
+
+    #[test]
+    fn start_in_wall_is_allowed() {
+        let g = grid(&["#..", "..."]);
+        assert_eq!(g.find_path((0, 0), (2, 0)), Some(vec![(1, 0), (2, 0)]));
+    }
+
+    #[test]
+    fn nearest_walkable_and_tile_coordinates() {
+        let g = grid(&["###", "#.#", "###"]);
+        assert_eq!(g.nearest_walkable((0, 0)), Some((1, 1)));
+        assert_eq!(g.nearest_walkable((5, 0)), None);
+        assert_eq!(g.walkable_in((0, 0), (3, 3)), vec![(1, 1)]);
+        assert_eq!(g.walkable_in((0, 0), (1, 9)), vec![]);
+        let map_start = mint::Point2 { x: 100.0, y: 164.0 };
+        assert_eq!(g.tile_of(mint::Point2 { x: 140.0, y: 200.0 }, map_start), Some((1, 1)));
+        assert_eq!(g.tile_of(mint::Point2 { x: 99.0, y: 200.0 }, map_start), None);
+        assert_eq!(g.point_of((1, 1), map_start), mint::Point2 { x: 132.0, y: 196.0 });
+    }
+}
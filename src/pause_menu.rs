@@ -0,0 +1,272 @@
+use std::collections::VecDeque;
+use ggez::*;
+use ggez::audio::SoundSource;
+
+use crate::bindings::Action;
+use crate::controls_panel::ControlsPanel;
+use crate::main_menu::MainMenu;
+use crate::scene::{Scene, Shared, Signal, Transition};
+use crate::screen;
+use crate::ui::{self, MenuInput};
+
+const PANEL_OFFSET: f32 = 390.0; // isto kao u LevelSelect
+const ROW_HEIGHT: f32 = 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PauseAction {
+    Resume,
+    Restart,
+    SaveAndQuit,
+    QuitToMenu,
+}
+
+// Meni koji se otvara na Escape tokom igre, preko zamrznute simulacije
+pub struct PauseMenu {
+    offset: f32,
+    stone_pos: mint::Point2<f32>,
+    title: graphics::Text,
+    title_pos: mint::Point2<f32>,
+    items: Vec<(PauseAction, graphics::Text)>, // Resume, Restart level, Save and quit, Quit to main menu
+    options: graphics::Text,
+    items_pos: mint::Point2<f32>,
+    music: graphics::Text,
+    controls: graphics::Text,
+    back: graphics::Text,
+    font: graphics::Font,
+    stone: graphics::Image,
+    stone_sound: audio::Source,
+    showing_options: bool,
+    was_pressed: bool, // da jedan klik ne bi dva puta promenio opciju
+    closing: Option<PauseAction>, // kamen se spusta, pa se vraca ova akcija
+    focus: Option<usize>, // red koji je zut, kao u MainMenu
+    menu_input: VecDeque<MenuInput>, // sve sto je stiglo od proslog updatea, obradjuje se redom
+    last_mouse: mint::Point2<f32>,
+}
+
+impl PauseMenu {
+    pub fn new(ctx: &mut Context, music_on: bool) -> GameResult<Self> {
+        let font_celtknot = graphics::Font::new(ctx, "/fonts/Celtknot.ttf")?;
+        let mut title = graphics::Text::new("Paused");
+        title.set_font(font_celtknot, graphics::Scale::uniform(60.0));
+        let mut items: Vec<(PauseAction, graphics::Text)> = Vec::new();
+        for &(action, label) in [(PauseAction::Resume, "Resume"), (PauseAction::Restart, "Restart level"), (PauseAction::SaveAndQuit, "Save and quit"), (PauseAction::QuitToMenu, "Quit to main menu")].iter() {
+            let mut text = graphics::Text::new(label);
+            text.set_font(font_celtknot, graphics::Scale::uniform(40.0));
+            items.push((action, text));
+        }
+        let mut options = graphics::Text::new("Options");
+        options.set_font(font_celtknot, graphics::Scale::uniform(40.0));
+        let mut controls = graphics::Text::new("Controls");
+        controls.set_font(font_celtknot, graphics::Scale::uniform(40.0));
+        let mut back = graphics::Text::new("Back");
+        back.set_font(font_celtknot, graphics::Scale::uniform(40.0));
+        let mut stone_sound = audio::Source::new(ctx, "/sounds/stone_short.mp3")?;
+        stone_sound.play_detached()?;
+
+        Ok(PauseMenu {
+            offset: PANEL_OFFSET,
+            stone_pos: mint::Point2 { x: 40.0, y: 40.0 },
+            title: title,
+            title_pos: mint::Point2 { x: 200.0, y: 70.0 },
+            items: items,
+            options: options,
+            items_pos: mint::Point2 { x: 150.0, y: 170.0 },
+            music: PauseMenu::music_text(font_celtknot, music_on),
+            controls: controls,
+            back: back,
+            font: font_celtknot,
+            stone: graphics::Image::new(ctx, "/images/user_interface.png")?,
+            stone_sound: stone_sound,
+            showing_options: false,
+            was_pressed: true,
+            closing: None,
+            focus: None,
+            menu_input: VecDeque::new(),
+            last_mouse: screen::mouse_position(ctx),
+        })
+    }
+
+    fn music_text(font: graphics::Font, music_on: bool) -> graphics::Text {
+        let mut text = graphics::Text::new(if music_on { "Music: On" } else { "Music: Off" });
+        text.set_font(font, graphics::Scale::uniform(40.0));
+        text
+    }
+
+    fn shifted(&self, pos: mint::Point2<f32>) -> mint::Point2<f32> {
+        mint::Point2 { x: pos.x, y: pos.y + self.offset }
+    }
+
+    fn row(&self, i: usize) -> mint::Point2<f32> {
+        mint::Point2 { x: self.items_pos.x, y: self.items_pos.y + i as f32*ROW_HEIGHT }
+    }
+
+    fn row_count(&self) -> usize {
+        // u opcijama su Music, Controls i Back, inace stavke pa Options
+        if self.showing_options { 3 } else { self.items.len() + 1 }
+    }
+
+    fn row_text(&self, i: usize) -> &graphics::Text {
+        if self.showing_options {
+            match i {
+                0 => &self.music,
+                1 => &self.controls,
+                _ => &self.back,
+            }
+        } else if i < self.items.len() {
+            &self.items[i].1
+        } else {
+            &self.options
+        }
+    }
+
+    fn row_text_mut(&mut self, i: usize) -> &mut graphics::Text {
+        if self.showing_options {
+            match i {
+                0 => &mut self.music,
+                1 => &mut self.controls,
+                _ => &mut self.back,
+            }
+        } else if i < self.items.len() {
+            &mut self.items[i].1
+        } else {
+            &mut self.options
+        }
+    }
+
+    fn show_options(&mut self, on: bool) {
+        // druga lista redova, stari fokus vise ne vazi
+        self.showing_options = on;
+        self.focus = None;
+    }
+
+    fn activate(&mut self, ctx: &mut Context, shared: &mut Shared, i: usize) -> GameResult<Transition> {
+        // isto za klik misem i za Enter ili dugme A
+        if self.showing_options {
+            match i {
+                0 => {
+                    shared.music_on = !shared.music_on;
+                    self.music = PauseMenu::music_text(self.font, shared.music_on);
+                },
+                1 => return Ok(Transition::Push(Box::new(ControlsPanel::new(ctx, &shared.bindings)?))),
+                _ => self.show_options(false),
+            }
+        } else if i < self.items.len() {
+            self.closing = Some(self.items[i].0);
+            self.stone_sound.play_detached()?;
+        } else {
+            self.show_options(true);
+        }
+        Ok(Transition::Stay)
+    }
+
+    fn close(&mut self) {
+        // Escape dok je meni otvoren isto sto i Resume
+        if self.closing.is_none() {
+            self.closing = Some(PauseAction::Resume);
+            self.stone_sound.play_detached().unwrap();
+        }
+    }
+
+}
+
+impl Scene for PauseMenu {
+    fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Transition> {
+        // opcija Music menja shared.music_on, main ukljucuje i iskljucuje pesmu
+        if let Some(action) = self.closing {
+            self.offset += 5.0;
+            if self.offset < PANEL_OFFSET {
+                return Ok(Transition::Stay)
+            }
+            return match action {
+                PauseAction::Resume => Ok(Transition::Pop),
+                PauseAction::Restart => Ok(Transition::PopWith(Signal::RestartLevel)),
+                PauseAction::SaveAndQuit => Ok(Transition::PopWith(Signal::SaveAndQuit)), // igra se cuva i sama izlazi u meni
+                PauseAction::QuitToMenu => Ok(Transition::Reset(Box::new(MainMenu::new(ctx, &shared.bindings)?))),
+            }
+        }
+        if self.offset > 0.0 {
+            self.offset -= 5.0;
+            return Ok(Transition::Stay)
+        }
+        let pressed = input::mouse::button_pressed(ctx, input::mouse::MouseButton::Left);
+        let clicked = pressed && !self.was_pressed;
+        self.was_pressed = pressed;
+
+        let hovered = (0..self.row_count()).find(|&i| ui::hovered(ctx, self.row_text(i), self.row(i)));
+        let mouse_pos = screen::mouse_position(ctx);
+        if mouse_pos != self.last_mouse {
+            // mis se pomerio, fokus prati mis
+            self.last_mouse = mouse_pos;
+            self.focus = hovered;
+        }
+        let mut transition = Transition::Stay;
+        while let Some(menu_input) = self.menu_input.pop_front() {
+            let count = self.row_count();
+            match menu_input {
+                MenuInput::Up => self.focus = Some(self.focus.map_or(count - 1, |f| (f + count - 1) % count)),
+                MenuInput::Down => self.focus = Some(self.focus.map_or(0, |f| (f + 1) % count)),
+                MenuInput::Confirm => if let Some(i) = self.focus {
+                    transition = self.activate(ctx, shared, i)?;
+                },
+                MenuInput::Back => if self.showing_options {
+                    self.show_options(false);
+                } else {
+                    self.close();
+                },
+                _ => (),
+            }
+            match transition {
+                Transition::Stay => (),
+                _ => self.menu_input.clear(), // posle prelaza ostatak unosa vise nije za pauzu
+            }
+        }
+        if clicked {
+            if let Some(i) = hovered {
+                transition = self.activate(ctx, shared, i)?;
+            }
+        }
+        for i in 0..self.row_count() {
+            let on = self.focus == Some(i);
+            ui::highlight(self.row_text_mut(i), on);
+        }
+        Ok(transition)
+    }
+
+    fn draw(&self, ctx: &mut Context, _shared: &mut Shared) -> GameResult<()> {
+        // igra se crta ispod, pa je prvo malo zatamnimo
+        let (w, h) = screen::VIRTUAL_SIZE; // crta se na platno, a ne direktno u prozor
+        let shade = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), [0.0, 0.0, w, h].into(), [0.0, 0.0, 0.0, 0.5].into())?;
+        graphics::draw(ctx, &shade, graphics::DrawParam::new())?;
+        graphics::draw(ctx, &self.stone, graphics::DrawParam::new().dest(self.shifted(self.stone_pos)))?;
+        graphics::draw(ctx, &self.title, graphics::DrawParam::new().dest(self.shifted(self.title_pos)))?;
+        if self.showing_options {
+            graphics::draw(ctx, &self.music, graphics::DrawParam::new().dest(self.shifted(self.row(0))))?;
+            graphics::draw(ctx, &self.controls, graphics::DrawParam::new().dest(self.shifted(self.row(1))))?;
+            graphics::draw(ctx, &self.back, graphics::DrawParam::new().dest(self.shifted(self.row(2))))?;
+            return Ok(())
+        }
+        for (i, (_, text)) in self.items.iter().enumerate() {
+            graphics::draw(ctx, text, graphics::DrawParam::new().dest(self.shifted(self.row(i))))?;
+        }
+        graphics::draw(ctx, &self.options, graphics::DrawParam::new().dest(self.shifted(self.row(self.items.len()))))?;
+        Ok(())
+    }
+
+    fn action(&mut self, action: Action) {
+        if action == Action::Pause {
+            self.close();
+        }
+    }
+
+    fn menu(&mut self, input: MenuInput) {
+        self.menu_input.push_back(input);
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn pauses_music(&self) -> bool {
+        true
+    }
+}
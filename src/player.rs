@@ -1,66 +1,161 @@
-use ggez::*;
-use ggez::audio::SoundSource;
+use ggez::mint;
 use ncollide2d::shape::{Compound};
 use ncollide2d::world::{CollisionObjectHandle, CollisionWorld};
-use ncollide2d::query::{Ray, RayCast};
+use ncollide2d::query::{self, Ray, RayCast};
 use na::{Vector2, Isometry2, Rotation2, Point2};
 
-use crate::anim::{Animation, Direction};
+use crate::anim::{Direction, Frames};
+use crate::noise::{Noise, NoiseKind};
 use crate::score::Score;
+use crate::sim::{Input, STICK_STEPS};
+
+const PUSH_ITERATIONS: usize = 4; // izlazak iz jednog zida moze da ugura igraca u susedni, pa se proverava vise puta
+const WALK_SPD: f32 = 4.0;
+const SNEAK_SPD: f32 = 2.0;
+const SPRINT_SPD: f32 = 6.0;
+const WALK_NOISE: f32 = 40.0; // koliko daleko se cuju koraci, sunjanje se ne cuje
+const SPRINT_NOISE: f32 = 120.0;
+const STAMINA_DRAIN: f32 = 1.0/90.0; // puna izdrzljivost traje 3 sekunde trcanja
+const STAMINA_REGEN: f32 = 1.0/150.0; // a puni se 5 sekundi
+const THROW_COOLDOWN: i32 = 30; // kamencic moze da se baci najvise jednom u sekundi
+
+// Nacin kretanja, bira se tasterima za sunjanje i trcanje
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gait {
+    Sneak,
+    Walk,
+    Sprint,
+}
 
 #[derive(Debug)]
 pub struct Player {
-    pos: mint::Point2<f32>,
+    pub pos: mint::Point2<f32>,
+    pub prev_pos: mint::Point2<f32>, // pozicija pre poslednjeg ticka, renderer crta izmedju nje i pos
     pub direction: Vector2<f32>,
-    pub collision_ver: Direction,
-    pub collision_hor: Direction,
     walking: bool,
-    run_left: Animation,
-    run_right: Animation,
-    run_down: Animation,
-    run_up: Animation,
-    idle: graphics::Image,
+    pub frames: Frames,
     pub animation_state: Direction,
     spd: f32,
+    pub gait: Gait,
+    pub stamina: f32, // od 0 do 1, trosi se dok igrac trci
+    facing: Vector2<f32>, // poslednji pravac kretanja, u tom pravcu se baca kamencic
+    throw_held: bool, // taster za bacanje je bio pritisnut u prethodnom ticku
+    throw_cooldown: i32,
     pub col_handle: CollisionObjectHandle,
-    visibility: Vec<mint::Point2<f32>>,
+    pub visibility: Vec<mint::Point2<f32>>,
     pub score: i32,
-    coin_sound: audio::Source,
-    pub caught: bool,
-    pub escaped: bool,
+    spotted: i32, // koliko puta su strazari primetili igraca u ovom nivou
 }
 
 impl Player {
-    pub fn new(ctx: &mut Context, handle: CollisionObjectHandle) -> Self {
+    pub fn new(handle: CollisionObjectHandle) -> Self {
         Player {
             pos: mint::Point2 {x: 350.0, y: 200.0},
+            prev_pos: mint::Point2 {x: 350.0, y: 200.0},
             direction: Vector2::new(0.0, 0.0),
-            collision_ver: Direction::Null,
-            collision_hor: Direction::Null,
             walking: false,
-            run_left: Animation::new(ctx, "/images/robin_runleft.png"),
-            run_right: Animation::new(ctx, "/images/robin_runright.png"),
-            run_down: Animation::new(ctx, "/images/robin_rundown.png"),
-            run_up: Animation::new(ctx, "/images/robin_runup.png"),
-            idle: graphics::Image::new(ctx, "/images/robin_idle.png").unwrap(),
+            frames: Frames::new(7.0),
             animation_state: Direction::Null,
-            spd: 4.0,
+            spd: WALK_SPD,
+            gait: Gait::Walk,
+            stamina: 1.0,
+            facing: Vector2::new(0.0, 1.0),
+            throw_held: false,
+            throw_cooldown: 0,
             col_handle: handle,
             visibility: Vec::new(),
             score: 0,
-            coin_sound: audio::Source::new(ctx, "/sounds/coins.wav").unwrap(),
-            caught: false,
-            escaped: false,
+            spotted: 0,
+        }
+    }
+
+    fn direction_from_input(&mut self, input: &Input) {
+        // smer kretanja racunamo iz palice ili pritisnutih tastera, zidove resava push_out.
+        // Otklon palice je i brzina, pa direction tada moze biti krace od 1
+        if input.stick_x != 0 || input.stick_y != 0 {
+            self.direction = Vector2::new(input.stick_x as f32/STICK_STEPS, input.stick_y as f32/STICK_STEPS);
+            return
+        }
+        self.direction.y = if input.up {
+            -1.0
+        } else if input.down {
+            1.0
+        } else {
+            0.0
+        };
+        self.direction.x = if input.left {
+            -1.0
+        } else if input.right {
+            1.0
+        } else {
+            0.0
+        };
+    }
+
+    fn gait_from_input(&mut self, input: &Input) {
+        // trcanje je moguce samo dok ima izdrzljivosti, a ona se puni kad igrac ne trci
+        let moving = self.direction.x != 0.0 || self.direction.y != 0.0;
+        self.gait = if input.sneak {
+            Gait::Sneak
+        } else if input.sprint && moving && self.stamina > 0.0 {
+            Gait::Sprint
+        } else {
+            Gait::Walk
+        };
+        if self.gait == Gait::Sprint {
+            self.stamina = (self.stamina - STAMINA_DRAIN).max(0.0);
+        } else if !input.sprint {
+            self.stamina = (self.stamina + STAMINA_REGEN).min(1.0); // drzanje tastera bez izdrzljivosti ne puni merac
+        }
+        self.spd = match self.gait {
+            Gait::Sneak => SNEAK_SPD,
+            Gait::Walk => WALK_SPD,
+            Gait::Sprint => SPRINT_SPD,
+        };
+    }
+
+    pub fn noise(&self) -> Option<Noise> {
+        // zvuk koraka u poslednjem ticku, iz sredine oblika igraca
+        if !self.walking {
+            return None
+        }
+        let radius = match self.gait {
+            Gait::Sneak => return None,
+            Gait::Walk => WALK_NOISE,
+            Gait::Sprint => SPRINT_NOISE,
+        };
+        let center = self.shape_pos(None).translation.vector;
+        Some(Noise { pos: mint::Point2 { x: center.x, y: center.y }, radius: radius, kind: NoiseKind::Footsteps })
+    }
+
+    pub fn throw(&mut self, input: &Input) -> Option<(mint::Point2<f32>, Vector2<f32>)> {
+        // odakle i u kom pravcu leti kamencic, ako je igrac u ovom ticku pritisnuo taster za bacanje.
+        // Drzanje tastera ne baca ponovo, a posle bacanja se ceka THROW_COOLDOWN tickova
+        if self.direction.x != 0.0 || self.direction.y != 0.0 {
+            self.facing = self.direction.normalize();
+        }
+        let pressed = input.throw && !self.throw_held;
+        self.throw_held = input.throw;
+        if self.throw_cooldown > 0 {
+            self.throw_cooldown -= 1;
+            return None
         }
+        if !pressed {
+            return None
+        }
+        self.throw_cooldown = THROW_COOLDOWN;
+        let center = self.shape_pos(None).translation.vector;
+        Some((mint::Point2 { x: center.x, y: center.y }, self.facing))
     }
 
     fn pos_from_move(&self) -> mint::Point2<f32> {
         // ova f-ja se poziva pri svakom apdejtu
         // na osnovu trenutne pozicije i pravca kretanja
         // vraca "sledecu" poziciju igraca
-        let mut norm_dir = Vector2::new(0.0, 0.0);
-        if self.direction.x != 0.0 || self.direction.y != 0.0 {
-            norm_dir = self.direction.normalize();
+        // dve strelice daju dijagonalu duzine vece od 1, pa je skracujemo da ne bi bila brza
+        let mut norm_dir = self.direction;
+        if norm_dir.norm() > 1.0 {
+            norm_dir = norm_dir.normalize();
         }
 
         mint::Point2 { x: self.pos.x + norm_dir.x * self.spd, y: self.pos.y + norm_dir.y * self.spd }
@@ -74,7 +169,41 @@ impl Player {
         }
     }
 
-    pub fn update(&mut self, ctx: &mut Context, world: &mut CollisionWorld<f32, ()>, map_handle: CollisionObjectHandle, corners: &mut Vec<mint::Point2<f32>>) -> Vector2<f32> {
+    fn push_out(&mut self, world: &CollisionWorld<f32, ()>, map_handle: CollisionObjectHandle) {
+        // ako je oblik igraca usao u zid, izbacujemo ga duz normale kontakta za dubinu preklapanja.
+        // Kontakt se racuna odmah, jer world.update se zove tek posle pomeranja svih objekata
+        let map_object = world.collision_object(map_handle).unwrap();
+        let player_object = world.collision_object(self.col_handle).unwrap();
+        for _i in 0..PUSH_ITERATIONS {
+            // normala je usmerena od zida ka igracu
+            match query::contact(map_object.position(), &**map_object.shape(), &self.shape_pos(None), &**player_object.shape(), 0.0) {
+                Some(ref contact) if contact.depth > 0.0 => {
+                    let normal = contact.normal.into_inner();
+                    self.pos.x += normal.x*contact.depth;
+                    self.pos.y += normal.y*contact.depth;
+                },
+                _ => break,
+            }
+        }
+    }
+
+    pub fn restore(&mut self, world: &mut CollisionWorld<f32, ()>, pos: mint::Point2<f32>, score: i32, spotted: i32) {
+        // stanje iz sacuvane igre, igrac stoji dok se ne pritisne taster
+        self.pos = pos;
+        self.prev_pos = pos;
+        self.score = score;
+        self.spotted = spotted;
+        self.direction = Vector2::new(0.0, 0.0);
+        self.walking = false;
+        self.stamina = 1.0;
+        self.facing = Vector2::new(0.0, 1.0);
+        self.throw_held = false;
+        self.throw_cooldown = 0;
+        self.animation_state = Direction::Null;
+        world.set_position(self.col_handle, self.shape_pos(None));
+    }
+
+    pub fn update(&mut self, input: &Input, world: &mut CollisionWorld<f32, ()>, map_handle: CollisionObjectHandle, corners: &mut Vec<mint::Point2<f32>>) {
         /* self.walking je korisno za animaciju
          * npr. if self.walking {
          *          curr_animation = walk_animation;
@@ -82,6 +211,9 @@ impl Player {
          *          curr_animation = idle_animation;
          *      }
          */
+        self.prev_pos = self.pos;
+        self.direction_from_input(input);
+        self.gait_from_input(input);
         self.visibility.clear();
         // corners.sort_by(|a, b| Rotation2::rotation_between(&Vector2::x(), &Vector2::new(a.x, a.y)).angle().partial_cmp(&Rotation2::rotation_between(&Vector2::x(), &Vector2::new(b.x, b.y)).angle()).unwrap());
 
@@ -99,210 +231,47 @@ impl Player {
         if self.direction.x == 0.0 && self.direction.y == 0.0 {
             self.walking = false;
             self.animation_state = Direction::Null;
-            self.run_right.reset();
-            self.run_left.reset();
-            self.run_down.reset();
-            self.run_up.reset();
+            self.frames.reset();
 
-        } else if self.direction.x == 1.0 && self.direction.y == 0.0 {
-            self.animation_state = Direction::Right;
-            self.walking = true;
-        }
-        else if self.direction.x == -1.0 && self.direction.y == 0.0 {
-            self.animation_state = Direction::Left;
-            self.walking = true;
-        }
-        else if self.direction.x == 0.0 && self.direction.y == 1.0 {
-            self.animation_state = Direction::Down;
-            self.walking = true;
-        }
-        else if self.direction.x == 0.0 && self.direction.y == -1.0 {
-            self.animation_state = Direction::Up;
+        } else {
+            // animacija prati osu po kojoj se igrac vise krece, na dijagonali ostaje prethodna
+            let (x, y) = (self.direction.x, self.direction.y);
+            if x.abs() > 2.0*y.abs() {
+                self.animation_state = if x > 0.0 { Direction::Right } else { Direction::Left };
+            } else if y.abs() > 2.0*x.abs() {
+                self.animation_state = if y > 0.0 { Direction::Down } else { Direction::Up };
+            }
             self.walking = true;
         }
-        else { self.walking = true; }
         if self.walking {
-            match self.animation_state {
-                Direction::Right => self.run_right.next_frame(),
-                Direction::Left => self.run_left.next_frame(),
-                Direction::Up => self.run_up.next_frame(),
-                Direction::Down => self.run_down.next_frame(),
-                Direction::Null => (),
-            }
-
-            // mora da postoji neki bolji nacin da se ovo uradi
-            let mut old_dir = self.direction.clone();
-            if self.collision_hor == Direction::Left && input::keyboard::is_key_pressed(ctx, event::KeyCode::Left) {
-                self.direction = old_dir;
-                self.direction.x = -1.0;
-                let new_pos = self.pos_from_move();
-                world.set_position(self.col_handle, self.shape_pos(Some(new_pos)));
-                match world.contact_pair(self.col_handle, map_handle, true) {
-                    Some(_) => (),
-                    None => {
-                        self.collision_hor = Direction::Null;
-                        old_dir.x = -1.0;
-                        ()
-                    },
-                }
-            }
-            if self.collision_hor == Direction::Right && input::keyboard::is_key_pressed(ctx, event::KeyCode::Right){
-                self.direction = old_dir;
-                self.direction.x = 1.0;
-                let new_pos = self.pos_from_move();
-                world.set_position(self.col_handle, self.shape_pos(Some(new_pos)));
-                match world.contact_pair(self.col_handle, map_handle, true) {
-                    Some(_) => (),
-                    None => {
-                        self.collision_hor = Direction::Null;
-                        old_dir.x = 1.0;
-                        ()
-                    },
-                }
-            }
-            if self.collision_ver == Direction::Up && input::keyboard::is_key_pressed(ctx, event::KeyCode::Up) {
-                self.direction = old_dir;
-                self.direction.y = -1.0;
-                let new_pos = self.pos_from_move();
-                world.set_position(self.col_handle, self.shape_pos(Some(new_pos)));
-                match world.contact_pair(self.col_handle, map_handle, true) {
-                    Some(_) => (),
-                    None => {
-                        self.collision_hor = Direction::Null;
-                        old_dir.y = -1.0;
-                        ()
-                    },
-                }
-            }
-            if self.collision_ver == Direction::Down && input::keyboard::is_key_pressed(ctx, event::KeyCode::Down) {
-                self.direction = old_dir;
-                self.direction.y = 1.0;
-                let new_pos = self.pos_from_move();
-                world.set_position(self.col_handle, self.shape_pos(Some(new_pos)));
-                match world.contact_pair(self.col_handle, map_handle, true) {
-                    Some(_) => (),
-                    None => {
-                        self.collision_hor = Direction::Null;
-                        old_dir.y = 1.0;
-                        ()
-                    },
-                }
-            }
-            self.direction = old_dir;
-            world.set_position(self.col_handle, self.shape_pos(None));
-            if self.collision_hor == Direction::Right && self.direction.x == -1.0 {
-                self.collision_hor = Direction::Null;
-            }
-            if self.collision_hor == Direction::Left && self.direction.x == 1.0 {
-                self.collision_hor = Direction::Null;
-            }
-            if self.collision_ver == Direction::Down && self.direction.y == -1.0 {
-                self.collision_ver = Direction::Null;
-            }
-            if self.collision_ver == Direction::Up && self.direction.y == 1.0 {
-                self.collision_ver = Direction::Null;
-            }
-
-            match world.contact_pair(self.col_handle, map_handle, true) {
-                Some(c) => {
-                    let col = c.3;
-                    let dcontact = &col.deepest_contact().unwrap().contact;
-                    let ddepth = dcontact.depth;
-                    let dvector = dcontact.normal.into_inner();
-                    if ddepth >= 0.0 && ddepth < 13.0 {
-                        if dvector.x == -1.0 && self.direction.x == 1.0 {
-                            self.collision_hor = Direction::Right;
-                            self.direction.x = 0.0;
-                            self.pos = mint::Point2 { x: self.pos.x-ddepth, y: self.pos.y };
-                        }
-                        if dvector.x == 1.0 && self.direction.x == -1.0 {
-                            self.collision_hor = Direction::Left;
-                            self.direction.x = 0.0;
-                            self.pos = mint::Point2 { x: self.pos.x-ddepth, y: self.pos.y };
-                        }
-                        if dvector.y == -1.0 && self.direction.y == 1.0 {
-                            self.collision_ver = Direction::Down;
-                            self.direction.y = 0.0;
-                            self.pos = mint::Point2 { x: self.pos.x, y: self.pos.y-ddepth };
-                        }
-                        if dvector.y == 1.0 && self.direction.y == -1.0 {
-                            self.collision_ver = Direction::Up;
-                            self.direction.y = 0.0;
-                            self.pos = mint::Point2 { x: self.pos.x, y: self.pos.y+ddepth};
-                        }
-                        // self.pos = mint::Point2 { x: self.pos.x+dvector.x*(ddepth), y: self.pos.y+dvector.y*(ddepth) };
-                    }
-                    // self.pos = self.pos_from_move();
-
-                    // world.set_position(self.col_handle, self.shape_pos(None));
-                    ()
-                },
-                None => {
-                    self.collision_ver = Direction::Null;
-                    self.collision_hor = Direction::Null;
-                    ()
-                }
-
+            if self.animation_state != Direction::Null {
+                self.frames.next_frame();
             }
+            // pomeramo se prvo po x pa po y i posle svakog pomeraja izbacujemo igraca iz zidova.
+            // Tako uz zid na jednoj osi igrac i dalje klizi po drugoj, a ne zapinje o uglove polja
             let new_pos = self.pos_from_move();
-            if new_pos.x > 440.0 || new_pos.x < 200.0 || new_pos.y > 280.0 || new_pos.y < 200.0 {
-                return self.direction
-            } else {
-                self.pos = new_pos;
-            }
+            let (dx, dy) = (new_pos.x - self.pos.x, new_pos.y - self.pos.y);
+            self.pos.x += dx;
+            self.push_out(world, map_handle);
+            self.pos.y += dy;
+            self.push_out(world, map_handle);
             world.set_position(self.col_handle, self.shape_pos(None));
         }
-        Vector2::new(0.0, 0.0)
-    }
-
-    pub fn draw_visibility(&self, ctx: &mut Context) -> GameResult<()> {
-        if self.visibility.len() > 0 && self.visibility[0] != self.visibility[1] {
-            let vis_clone = self.visibility.clone();
-
-            let vis_mesh_test: graphics::Mesh = graphics::Mesh::new_polygon(ctx, graphics::DrawMode::fill(), &vis_clone, [0.0, 1.0, 0.0, 0.5].into())?;
-            // let mut whole_screen: graphics::Mesh = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), [0.0, 0.0, SCREEN_SIZE.0, SCREEN_SIZE.1].into(), [0.0, 0.0, 0.0, 1.0].into())?;
-            // whole_screen.set_blend_mode(Some(graphics::BlendMode::Subtract));
-            // vis_mesh_test.set_blend_mode(Some(graphics::BlendMode::Invert));
-            // let vis_mesh = vis_mesh_builder.build(ctx)?;
-            // graphics::draw(ctx, &whole_screen, graphics::DrawParam::new())?;
-            graphics::draw(ctx, &vis_mesh_test, graphics::DrawParam::new())?;
-        }
-        Ok(())
-    }
-
-    pub fn draw(&self, ctx: &mut Context, show_mesh: bool) -> GameResult<()> {
-        match self.animation_state {
-            Direction::Right => self.run_right.draw(ctx, self.pos)?,
-            Direction::Left => self.run_left.draw(ctx, self.pos)?,
-            Direction::Up => self.run_up.draw(ctx, self.pos)?,
-            Direction::Down => self.run_down.draw(ctx, self.pos)?,
-            Direction::Null => graphics::draw(ctx, &self.idle, graphics::DrawParam::new().dest(self.pos))?,
-        }
-        if show_mesh {
-            let shape_mesh = graphics::MeshBuilder::new().rectangle(graphics::DrawMode::stroke(3.0), graphics::Rect::new(self.shape_pos(None).translation.vector.x, self.shape_pos(None).translation.vector.y, 24.0, 16.0), [1.0, 0.0, 0.0, 1.0].into()).build(ctx)?;
-            graphics::draw(ctx, &shape_mesh, graphics::DrawParam::new())?;
-        }
-        Ok(())
     }
 }
 
 impl Score for Player {
-    fn increase (&mut self, coin: i32) -> GameResult<()> {
+    fn increase (&mut self, coin: i32) {
         self.score = self.score + coin;
-        if coin > 0 {
-            self.coin_sound.play_detached()?;
-        }
-        Ok(())
     }
-    fn draw_score (&self, ctx: &mut Context ) -> GameResult<()> {
-        let high_score = format!("Level 1     Gold collected: {}", self.score);
-        let mut tekst = graphics::Text::new (high_score);
-        let font_meath = graphics::Font::new(ctx, "/fonts/MeathFLF.ttf")?;
-        let interface_stone = graphics::Image::new (ctx, "/images/user_interface.png")?;
-        tekst.set_font(font_meath, graphics::Scale::uniform(20.0));
-        graphics::draw (ctx, &interface_stone, graphics::DrawParam::new().dest(mint::Point2{x: 40.0 , y: 432.0}))?;
-        graphics::draw (ctx, &tekst, graphics::DrawParam::new().dest(mint::Point2{x: 200.0 , y: 455.0}))?;
-        Ok(())
+    fn total (&self) -> i32 {
+        self.score
+    }
+    fn spotted (&mut self) {
+        self.spotted += 1;
+    }
+    fn times_spotted (&self) -> i32 {
+        self.spotted
     }
 }
 // zlato razlicite velicine nosi razlicit Score
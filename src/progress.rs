@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use ggez::{filesystem, Context, GameResult};
+
+pub const PROGRESS_FILENAME: &str = "progress.txt";
+
+#[derive(Debug, Clone, Default)]
+pub struct LevelRecord {
+    pub best: i32, // najvise zlata skupljenog u ovom nivou, sa bonusima za bekstvo
+    pub completed: bool, // da li je igrac bar jednom pobegao kroz vrata
+}
+
+// Napredak po nivoima, cuva se u ggez direktorijumu za korisnicke podatke.
+// Svaka linija fajla je "fajl_nivoa najbolji_rezultat predjen(0/1)"
+#[derive(Debug, Clone)]
+pub struct Progress {
+    records: HashMap<String, LevelRecord>,
+    path: PathBuf,
+}
+
+impl Progress {
+    pub fn load(ctx: &Context) -> Self {
+        // ako fajl ne postoji (prvo pokretanje) ili je neka linija ostecena, krecemo od nule
+        let path = filesystem::user_data_dir(ctx).join(PROGRESS_FILENAME);
+        let mut records: HashMap<String, LevelRecord> = HashMap::new();
+        if let Ok(text) = fs::read_to_string(&path) {
+            for line in text.lines() {
+                let fields: Vec<&str> = line.split(' ').collect();
+                if fields.len() != 3 {
+                    continue;
+                }
+                if let (Ok(best), Ok(completed)) = (fields[1].parse::<i32>(), fields[2].parse::<i32>()) {
+                    records.insert(fields[0].to_string(), LevelRecord { best: best, completed: completed != 0 });
+                }
+            }
+        }
+        Progress {
+            records: records,
+            path: path,
+        }
+    }
+
+    pub fn record(&self, level_file: &str) -> LevelRecord {
+        self.records.get(level_file).cloned().unwrap_or_default()
+    }
+
+    pub fn update(&mut self, level_file: &str, score: i32, completed: bool) {
+        let record = self.records.entry(level_file.to_string()).or_insert_with(LevelRecord::default);
+        record.best = record.best.max(score);
+        record.completed = record.completed || completed;
+    }
+
+    pub fn save(&self) -> GameResult<()> {
+        let mut files: Vec<&String> = self.records.keys().collect();
+        files.sort(); // da bi fajl uvek izgledao isto
+        let mut text = String::new();
+        for file in files {
+            let record = &self.records[file];
+            text.push_str(&format!("{} {} {}\n", file, record.best, if record.completed { 1 } else { 0 }));
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, text)?;
+        Ok(())
+    }
+}
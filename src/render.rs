@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use ggez::*;
+use na::Vector2;
+
+use crate::anim::{Animation, Direction};
+use crate::camera::ZOOM_LEVELS;
+use crate::guard::{Alertness, Guard};
+use crate::map::{Map, TileType, Treasure, TILE_SIZE};
+use crate::player::{Gait, Player};
+use crate::score::Score;
+use crate::screen::VIRTUAL_SIZE;
+use crate::sim::{Simulation, TICKS_PER_SECOND};
+
+pub fn tick_fraction(ctx: &Context) -> f32 {
+    // koliki deo sledeceg ticka je vec protekao, od 0 do 1
+    let remaining = timer::duration_to_f64(timer::remaining_update_time(ctx)) as f32;
+    (remaining*TICKS_PER_SECOND).min(1.0)
+}
+
+fn lerp(prev: mint::Point2<f32>, curr: mint::Point2<f32>, alpha: f32) -> mint::Point2<f32> {
+    mint::Point2 { x: prev.x + (curr.x - prev.x)*alpha, y: prev.y + (curr.y - prev.y)*alpha }
+}
+
+fn moved(point: mint::Point2<f32>, offset: Vector2<f32>) -> mint::Point2<f32> {
+    mint::Point2 { x: point.x + offset.x, y: point.y + offset.y }
+}
+
+// Sve slike i fontovi koji su potrebni da se nacrta simulacija.
+// Simulacija ne zna za njih, renderer samo cita njeno stanje
+pub struct Renderer {
+    map_spritebatch: graphics::spritebatch::SpriteBatch,
+    tile_size: mint::Point2<f32>,
+    sheet_size: mint::Point2<f32>,
+    robin_run_left: Animation,
+    robin_run_right: Animation,
+    robin_run_down: Animation,
+    robin_run_up: Animation,
+    robin_idle: graphics::Image,
+    guard_run_left: Animation,
+    guard_run_right: Animation,
+    guard_run_down: Animation,
+    guard_run_up: Animation,
+    gold_images: HashMap<String, graphics::Image>, // slike blaga po putanji iz Treasure
+    door_image: graphics::Image,
+    interface_stone: graphics::Image,
+    font_meath: graphics::Font,
+    pub show_paths: bool, // debug prikaz A* putanja strazara (F3)
+    pub zoom: f32, // zoom kamere u igri, menja se tasterima + i -
+}
+
+impl Renderer {
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        let spritesheet = graphics::Image::new(ctx, "/images/castle_spritesheet.png")?;
+        let sheet_size = mint::Point2 { x: spritesheet.width() as f32, y: spritesheet.height() as f32 };
+        let mut gold_images: HashMap<String, graphics::Image> = HashMap::new();
+        for treasure in Treasure::defaults().iter() {
+            gold_images.insert(treasure.sprite.clone(), graphics::Image::new(ctx, &treasure.sprite)?);
+        }
+        Ok(Renderer {
+            map_spritebatch: graphics::spritebatch::SpriteBatch::new(spritesheet),
+            tile_size: mint::Point2 { x: TILE_SIZE, y: TILE_SIZE },
+            sheet_size: sheet_size,
+            robin_run_left: Animation::new(ctx, "/images/robin_runleft.png"),
+            robin_run_right: Animation::new(ctx, "/images/robin_runright.png"),
+            robin_run_down: Animation::new(ctx, "/images/robin_rundown.png"),
+            robin_run_up: Animation::new(ctx, "/images/robin_runup.png"),
+            robin_idle: graphics::Image::new(ctx, "/images/robin_idle.png")?,
+            guard_run_left: Animation::new(ctx, "/images/guard_runleft.png"),
+            guard_run_right: Animation::new(ctx, "/images/guard_runright.png"),
+            guard_run_down: Animation::new(ctx, "/images/guard_rundown.png"),
+            guard_run_up: Animation::new(ctx, "/images/guard_runup.png"),
+            gold_images: gold_images,
+            door_image: graphics::Image::new(ctx, "/images/castle_door.png")?,
+            interface_stone: graphics::Image::new(ctx, "/images/user_interface.png")?,
+            font_meath: graphics::Font::new(ctx, "/fonts/MeathFLF.ttf")?,
+            show_paths: false,
+            zoom: 1.0,
+        })
+    }
+
+    fn zoom_step(&mut self, step: i32) {
+        let current = ZOOM_LEVELS.iter().position(|z| *z == self.zoom).unwrap_or(1) as i32;
+        let next = (current + step).max(0).min(ZOOM_LEVELS.len() as i32 - 1);
+        self.zoom = ZOOM_LEVELS[next as usize];
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.zoom_step(1);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.zoom_step(-1);
+    }
+
+    pub fn tile_fraction(&self) -> mint::Point2<f32> {
+        // velicina jednog polja kao deo spritesheeta, treba za Map::load
+        mint::Point2 { x: self.tile_size.x/self.sheet_size.x, y: self.tile_size.y/self.sheet_size.y }
+    }
+
+    pub fn load_treasures(&mut self, ctx: &mut Context, map: &Map) -> GameResult<()> {
+        // ucitava slike za vrste blaga koje je nivo definisao, a jos nisu ucitane
+        for treasure in map.map_treasures.iter() {
+            if !self.gold_images.contains_key(&treasure.sprite) {
+                self.gold_images.insert(treasure.sprite.clone(), graphics::Image::new(ctx, &treasure.sprite)?);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, sim: &Simulation, level_name: &str, alpha: f32) -> GameResult<()> {
+        // bitan je redosled navodjenja pojedinacnih draw funkcija.
+        // alpha je deo ticka koji je protekao od poslednjeg stepa, sve se crta izmedju
+        // prethodne i trenutne pozicije da kretanje ne bi seckalo kad je FPS veci od 30
+        // Sve iz simulacije je u koordinatama sveta, scroll ih prebacuje na ekran
+        let scroll = sim.camera.offset(alpha);
+        // zoom se primenjuje na sve sto je u svetu, ali ne i na kamen sa rezultatom
+        graphics::push_transform(ctx, Some(graphics::DrawParam::new().scale([sim.camera.zoom, sim.camera.zoom]).to_matrix()));
+        graphics::apply_transformations(ctx)?;
+        self.draw_map(ctx, &sim.castle_map, scroll, 1, false)?; // crta prvi sloj mape (podovi, zidovi iza igraca)
+        graphics::draw(ctx, &self.door_image, graphics::DrawParam::new().dest(moved(sim.castle_map.map_door.pos, scroll)))?; // crta vrata
+        self.draw_gold(ctx, &sim.castle_map, scroll)?; // prodje kroz ceo vektor i nacrta svaki element
+        self.draw_pebbles(ctx, &sim.castle_map, scroll, alpha)?;
+        self.draw_player(ctx, &sim.player, scroll, alpha, false)?;
+        self.draw_guards(ctx, &sim.castle_map, scroll, alpha)?;
+        self.draw_map(ctx, &sim.castle_map, scroll, 2, false)?; // crta drugi sloj mape (donji zidovi)
+        self.draw_guard_vision(ctx, &sim.castle_map, scroll, alpha)?; // vidno polje strazara
+        if self.show_paths {
+            self.draw_guard_paths(ctx, &sim.castle_map, scroll, alpha)?;
+        }
+        graphics::pop_transform(ctx);
+        graphics::apply_transformations(ctx)?;
+        self.draw_score(ctx, &sim.player, level_name, sim.seed)?;
+        // self.draw_visibility(ctx, &sim.player, scroll)?;
+        Ok(())
+    }
+
+    pub fn draw_map(&mut self, ctx: &mut Context, map: &Map, scroll: Vector2<f32>, layer: i32, show_mesh: bool) -> GameResult<()> {
+        let map_start = moved(map.map_start, scroll);
+        for row in map.map_matrix.iter() {
+            for tile in row.iter() {
+                if tile.tile_layer == layer {
+                    self.map_spritebatch.add(tile.drawparam(map_start));
+                }
+            }
+        }
+        self.map_spritebatch.set_filter(graphics::FilterMode::Nearest);
+        graphics::draw(ctx, &self.map_spritebatch, graphics::DrawParam::new())?;
+        self.map_spritebatch.clear();
+
+        if show_mesh {
+            let mut tile_mesh = graphics::MeshBuilder::new();
+            for row in map.map_matrix.iter() {
+                for tile in row.iter() {
+                    if tile.tile_layer == layer {
+                        match tile.tile_type {
+                            TileType::Wall(_, _) => {
+                                tile_mesh.rectangle(graphics::DrawMode::stroke(3.0), [tile.drawparam(map_start).dest.x, tile.drawparam(map_start).dest.y, tile.tile_size.x, tile.tile_size.y].into(), [0.0, 1.0, 0.0, 1.0].into());
+                                ()
+                            },
+                            TileType::Floor(_, _) => (),
+                        }
+                    }
+                }
+            }
+            let built_mesh = tile_mesh.build(ctx)?;
+            graphics::draw(ctx, &built_mesh, graphics::DrawParam::new())?;
+        }
+        Ok(())
+    }
+
+    pub fn draw_gold(&self, ctx: &mut Context, map: &Map, scroll: Vector2<f32>) -> GameResult<()> {
+        for gold in map.map_gold.iter() {
+            if let Some(image) = self.gold_images.get(&gold.sprite) {
+                graphics::draw(ctx, image, graphics::DrawParam::new().dest(moved(gold.pos, scroll)))?;
+            }
+        }
+        Ok(())
+        // ova funkcija crta na ekran sve zlatnike
+    }
+
+    pub fn draw_pebbles(&self, ctx: &mut Context, map: &Map, scroll: Vector2<f32>, alpha: f32) -> GameResult<()> {
+        // kamencici u letu, pali kamencici se ne crtaju
+        if map.map_pebbles.is_empty() {
+            return Ok(())
+        }
+        let mut pebbles = graphics::MeshBuilder::new();
+        for pebble in map.map_pebbles.iter() {
+            pebbles.circle(graphics::DrawMode::fill(), moved(lerp(pebble.prev_pos, pebble.pos, alpha), scroll), 3.0, 0.5, [0.6, 0.6, 0.6, 1.0].into());
+        }
+        let built_mesh = pebbles.build(ctx)?;
+        graphics::draw(ctx, &built_mesh, graphics::DrawParam::new())
+    }
+
+    pub fn draw_player(&self, ctx: &mut Context, player: &Player, scroll: Vector2<f32>, alpha: f32, show_mesh: bool) -> GameResult<()> {
+        let pos = moved(lerp(player.prev_pos, player.pos, alpha), scroll);
+        match player.animation_state {
+            Direction::Right => self.robin_run_right.draw(ctx, pos, &player.frames)?,
+            Direction::Left => self.robin_run_left.draw(ctx, pos, &player.frames)?,
+            Direction::Up => self.robin_run_up.draw(ctx, pos, &player.frames)?,
+            Direction::Down => self.robin_run_down.draw(ctx, pos, &player.frames)?,
+            Direction::Null => graphics::draw(ctx, &self.robin_idle, graphics::DrawParam::new().dest(pos))?,
+        }
+        if show_mesh {
+            let shape = player.shape_pos(None).translation.vector + scroll;
+            let shape_mesh = graphics::MeshBuilder::new().rectangle(graphics::DrawMode::stroke(3.0), graphics::Rect::new(shape.x, shape.y, 24.0, 16.0), [1.0, 0.0, 0.0, 1.0].into()).build(ctx)?;
+            graphics::draw(ctx, &shape_mesh, graphics::DrawParam::new())?;
+        }
+        Ok(())
+    }
+
+    pub fn draw_visibility(&self, ctx: &mut Context, player: &Player, scroll: Vector2<f32>) -> GameResult<()> {
+        if player.visibility.len() > 0 && player.visibility[0] != player.visibility[1] {
+            let vis_clone: Vec<mint::Point2<f32>> = player.visibility.iter().map(|p| moved(*p, scroll)).collect();
+
+            let vis_mesh_test: graphics::Mesh = graphics::Mesh::new_polygon(ctx, graphics::DrawMode::fill(), &vis_clone, [0.0, 1.0, 0.0, 0.5].into())?;
+            graphics::draw(ctx, &vis_mesh_test, graphics::DrawParam::new())?;
+        }
+        Ok(())
+    }
+
+    fn draw_guard(&self, ctx: &mut Context, guard: &Guard, scroll: Vector2<f32>, alpha: f32) -> GameResult<()> {
+        let pos = moved(lerp(guard.prev_pos, guard.pos, alpha), scroll);
+        match guard.animation_state {
+            Direction::Right => self.guard_run_right.draw(ctx, pos, &guard.frames)?,
+            Direction::Left => self.guard_run_left.draw(ctx, pos, &guard.frames)?,
+            Direction::Up => self.guard_run_up.draw(ctx, pos, &guard.frames)?,
+            Direction::Down => self.guard_run_down.draw(ctx, pos, &guard.frames)?,
+            Direction::Null => (),
+        }
+        if guard.detection > 0.0 {
+            // merac iznad glave strazara, puni se dok strazar gleda igraca
+            let meter = graphics::MeshBuilder::new()
+                .rectangle(graphics::DrawMode::fill(), graphics::Rect::new(pos.x+4.0, pos.y-6.0, 24.0, 4.0), [0.0, 0.0, 0.0, 0.6].into())
+                .rectangle(graphics::DrawMode::fill(), graphics::Rect::new(pos.x+4.0, pos.y-6.0, 24.0*guard.detection, 4.0), [1.0, 0.8, 0.1, 1.0].into())
+                .build(ctx)?;
+            graphics::draw(ctx, &meter, graphics::DrawParam::new())?;
+        }
+        Ok(())
+    }
+
+    pub fn draw_guards(&self, ctx: &mut Context, map: &Map, scroll: Vector2<f32>, alpha: f32) -> GameResult<()> {
+        for guard in map.map_guards.iter() {
+            self.draw_guard(ctx, guard, scroll, alpha)?;
+        }
+        Ok(())
+    }
+
+    pub fn draw_guard_vision(&self, ctx: &mut Context, map: &Map, scroll: Vector2<f32>, alpha: f32) -> GameResult<()> {
+        for guard in map.map_guards.iter() {
+            if guard.vision.len() < 3 {
+                continue; // strazar se jos nije ni jednom apdejtovao
+            }
+            // lepeza se pomera zajedno sa strazarem
+            let pos = lerp(guard.prev_pos, guard.pos, alpha);
+            let offset = Vector2::new(pos.x - guard.pos.x, pos.y - guard.pos.y) + scroll;
+            // vidno polje je lepeza, svaki par susednih zrakova sa ocima strazara cini trougao
+            let mut triangles: Vec<mint::Point2<f32>> = Vec::new();
+            for i in 1..guard.vision.len()-1 {
+                triangles.push(moved(guard.vision[0], offset));
+                triangles.push(moved(guard.vision[i], offset));
+                triangles.push(moved(guard.vision[i+1], offset));
+            }
+            let color: graphics::Color = match guard.alertness() {
+                Alertness::Calm => [1.0, 0.0, 0.0, 0.5].into(),
+                Alertness::Suspicious => [1.0, 0.8, 0.1, 0.5].into(),
+                Alertness::Searching => [1.0, 0.5, 0.0, 0.5].into(),
+                Alertness::Alert | Alertness::Caught => [1.0, 0.0, 0.0, 0.8].into(),
+            };
+            let vision = graphics::Mesh::from_triangles(ctx, &triangles, color)?;
+            graphics::draw(ctx, &vision, graphics::DrawParam::new())?;
+        }
+        Ok(())
+    }
+
+    pub fn draw_guard_paths(&self, ctx: &mut Context, map: &Map, scroll: Vector2<f32>, alpha: f32) -> GameResult<()> {
+        // linija od strazara kroz centre polja njegove A* putanje
+        let map_start = moved(map.map_start, scroll);
+        for guard in map.map_guards.iter() {
+            if guard.path.is_empty() {
+                continue;
+            }
+            let pos = moved(lerp(guard.prev_pos, guard.pos, alpha), scroll);
+            let mut points: Vec<mint::Point2<f32>> = vec![mint::Point2 { x: pos.x+16.0, y: pos.y+16.0 }];
+            for tile in guard.path.iter() {
+                let corner = map.map_grid.point_of(*tile, map_start);
+                points.push(mint::Point2 { x: corner.x+16.0, y: corner.y+16.0 });
+            }
+            let line = graphics::Mesh::new_line(ctx, &points, 2.0, [0.2, 0.6, 1.0, 0.8].into())?;
+            graphics::draw(ctx, &line, graphics::DrawParam::new())?;
+        }
+        Ok(())
+    }
+
+    pub fn draw_score(&self, ctx: &mut Context, player: &Player, level_name: &str, seed: u64) -> GameResult<()> {
+        let high_score = format!("{}     Gold collected: {}", level_name, player.total());
+        let mut tekst = graphics::Text::new (high_score);
+        tekst.set_font(self.font_meath, graphics::Scale::uniform(20.0));
+        let mut seed_tekst = graphics::Text::new (format!("Seed {}", seed)); // da bi partija mogla da se ponovi sa --seed
+        seed_tekst.set_font(self.font_meath, graphics::Scale::uniform(14.0));
+        // kamen je prikacen za dno platna, a tekstovi za kamen
+        let stone_pos = mint::Point2 { x: (VIRTUAL_SIZE.0 - self.interface_stone.width() as f32)/2.0, y: VIRTUAL_SIZE.1 - 48.0 };
+        graphics::draw (ctx, &self.interface_stone, graphics::DrawParam::new().dest(stone_pos))?;
+        graphics::draw (ctx, &tekst, graphics::DrawParam::new().dest(mint::Point2{x: stone_pos.x + 160.0 , y: stone_pos.y + 23.0}))?;
+        graphics::draw (ctx, &seed_tekst, graphics::DrawParam::new().dest(mint::Point2{x: stone_pos.x + 30.0 , y: stone_pos.y + 26.0}))?;
+        // izdrzljivost za trcanje, desno na kamenu
+        let stamina_color: graphics::Color = if player.gait == Gait::Sprint { [1.0, 0.5, 0.0, 1.0].into() } else { [0.3, 0.8, 0.3, 1.0].into() };
+        let stamina = graphics::MeshBuilder::new()
+            .rectangle(graphics::DrawMode::fill(), graphics::Rect::new(stone_pos.x + 450.0, stone_pos.y + 28.0, 80.0, 8.0), [0.0, 0.0, 0.0, 0.6].into())
+            .rectangle(graphics::DrawMode::fill(), graphics::Rect::new(stone_pos.x + 450.0, stone_pos.y + 28.0, 80.0*player.stamina, 8.0), stamina_color)
+            .build(ctx)?;
+        graphics::draw(ctx, &stamina, graphics::DrawParam::new())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use ggez::{filesystem, Context, GameResult};
+
+use crate::level::{self, LevelError};
+use crate::sim::Input;
+
+pub const REPLAY_FILENAME: &str = "replay.txt";
+
+// Jedan zapis snimka: stanje tastera za jedan tick ili ponovno pokretanje nivoa iz pauze
+#[derive(Debug, Clone)]
+pub enum ReplayStep {
+    Tick(Input),
+    Restart,
+}
+
+// Snimak partije. Fajl pocinje linijama "seed broj" i "level fajl_nivoa" (ili "level campaign"),
+// a zatim je svaka linija jedan tick: slova u, d, l, r, e, q (sunjanje), s (trcanje) i t (bacanje) za pritisnute tastere,
+// pa "ax,y" za otklon palice ako nije na nuli, "-" ako nista nije pritisnuto, ili "restart". Simulacija zavisi samo od seeda i unosa, pa
+// isti snimak uvek daje istu partiju
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub seed: u64,
+    pub level_file: Option<String>, // None je cela kampanja
+    pub steps: Vec<ReplayStep>,
+}
+
+fn encode(input: &Input) -> String {
+    let mut line = String::new();
+    for &(pressed, c) in [(input.up, 'u'), (input.down, 'd'), (input.left, 'l'), (input.right, 'r'), (input.interact, 'e'),
+                             (input.sneak, 'q'), (input.sprint, 's'), (input.throw, 't')].iter() {
+        if pressed {
+            line.push(c);
+        }
+    }
+    if input.stick_x != 0 || input.stick_y != 0 {
+        line.push_str(&format!("a{},{}", input.stick_x, input.stick_y));
+    }
+    if line.is_empty() {
+        line.push('-');
+    }
+    line
+}
+
+fn decode_stick(file: &str, line_no: usize, column: usize, text: &str, input: &mut Input) -> Result<(), LevelError> {
+    // "x,y" posle slova a, otklon palice kao u Input
+    let fields = level::split_fields(text, ',');
+    if fields.len() != 2 {
+        return Err(LevelError::new(file, line_no, column, format!("stick: expected 'x,y', found '{}'", text)))
+    }
+    input.stick_x = level::parse_field(file, line_no, column + fields[0].0 - 1, fields[0].1, "stick x")?;
+    input.stick_y = level::parse_field(file, line_no, column + fields[1].0 - 1, fields[1].1, "stick y")?;
+    Ok(())
+}
+
+fn decode(file: &str, line_no: usize, line: &str) -> Result<Input, LevelError> {
+    let mut input = Input::default();
+    if line == "-" {
+        return Ok(input)
+    }
+    for (i, (byte, c)) in line.char_indices().enumerate() {
+        match c {
+            'a' => return decode_stick(file, line_no, i+2, &line[byte+1..], &mut input).map(|_| input),
+            'u' => input.up = true,
+            'd' => input.down = true,
+            'l' => input.left = true,
+            'r' => input.right = true,
+            'e' => input.interact = true,
+            'q' => input.sneak = true,
+            's' => input.sprint = true,
+            't' => input.throw = true,
+            _ => return Err(LevelError::new(file, line_no, i+1, format!("unknown key '{}' in replay tick", c))),
+        }
+    }
+    Ok(input)
+}
+
+impl Replay {
+    pub fn load(path: &str) -> GameResult<Self> {
+        // putanja je sa komandne linije, pa fajl citamo van ggez filesystema
+        let text = fs::read_to_string(path)?;
+        let mut lines = text.lines().enumerate().map(|(i, l)| (i+1, l.trim()));
+        let seed = match lines.next() {
+            Some((n, line)) if line.starts_with("seed ") => level::parse_field(path, n, 6, &line[5..], "seed")?,
+            _ => return Err(LevelError::new(path, 1, 1, "expected 'seed <number>'".to_string()).into()),
+        };
+        let level_file = match lines.next() {
+            Some((_, "level campaign")) => None,
+            Some((_, line)) if line.starts_with("level ") => Some(line[6..].to_string()),
+            _ => return Err(LevelError::new(path, 2, 1, "expected 'level <file>' or 'level campaign'".to_string()).into()),
+        };
+        let mut steps: Vec<ReplayStep> = Vec::new();
+        for (n, line) in lines {
+            if line == "restart" {
+                steps.push(ReplayStep::Restart);
+            } else if !line.is_empty() {
+                steps.push(ReplayStep::Tick(decode(path, n, line)?));
+            }
+        }
+        Ok(Replay {
+            seed: seed,
+            level_file: level_file,
+            steps: steps,
+        })
+    }
+}
+
+// Upisuje partiju u replay.txt u direktorijumu za korisnicke podatke, tick po tick,
+// da bi snimak postojao i kad se igra prekine usred nivoa. Svaka linija se odmah prazni
+// u fajl, pa ni pad igre ne gubi poslednje tickove. Svaka nova partija brise stari snimak
+pub struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn start(ctx: &Context, seed: u64, level_file: Option<&str>) -> GameResult<Self> {
+        Recorder::create(&filesystem::user_data_dir(ctx).join(REPLAY_FILENAME), seed, level_file)
+    }
+
+    fn create(path: &Path, seed: u64, level_file: Option<&str>) -> GameResult<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut recorder = Recorder { out: BufWriter::new(File::create(path)?) };
+        recorder.write(&format!("seed {}\nlevel {}", seed, level_file.unwrap_or("campaign")))?;
+        Ok(recorder)
+    }
+
+    fn write(&mut self, line: &str) -> GameResult<()> {
+        writeln!(self.out, "{}", line)?;
+        self.out.flush()?;
+        Ok(())
+    }
+
+    pub fn tick(&mut self, input: &Input) -> GameResult<()> {
+        self.write(&encode(input))
+    }
+
+    pub fn restart(&mut self) -> GameResult<()> {
+        self.write("restart")
+    }
+}
+
+// Pusta snimak korak po korak umesto tastature
+pub struct Playback {
+    steps: Vec<ReplayStep>,
+    next: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Playback {
+            steps: replay.steps,
+            next: 0,
+        }
+    }
+
+    pub fn next_step(&mut self) -> Option<ReplayStep> {
+        // None kad je snimak gotov
+        let step = self.steps.get(self.next).cloned();
+        self.next += 1;
+        step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_decode_round_trip() {
+        for &line in ["-", "u", "dlq", "rest", "udlreqst", "a100,-37", "sa-5,80"].iter() {
+            assert_eq!(encode(&decode("replay.txt", 3, line).unwrap()), line);
+        }
+        let input = decode("replay.txt", 3, "ts").unwrap();
+        assert!(input.throw && input.sprint && !input.up);
+    }
+
+    #[test]
+    fn unknown_key_points_at_column() {
+        let e = decode("replay.txt", 7, "x").unwrap_err();
+        assert_eq!((e.line, e.column), (7, 1));
+        let e = decode("replay.txt", 8, "ux").unwrap_err();
+        assert_eq!(e.to_string(), "replay.txt:8:2: unknown key 'x' in replay tick");
+        let e = decode("replay.txt", 9, "sa12").unwrap_err();
+        assert_eq!((e.line, e.column), (9, 3));
+        let e = decode("replay.txt", 9, "sa12,y").unwrap_err();
+        assert_eq!(e.to_string(), "replay.txt:9:6: stick y: expected a number, found 'y'");
+    }
+
+    #[test]
+    fn recorder_writes_each_tick_through() {
+        // fajl se cita dok je Recorder jos ziv, kao posle pada igre
+        let path = std::env::temp_dir().join(format!("robin_hood_replay_{}.txt", std::process::id()));
+        let mut recorder = Recorder::create(&path, 7, Some("level2.txt")).unwrap();
+        recorder.tick(&decode("replay.txt", 3, "ur").unwrap()).unwrap();
+        recorder.restart().unwrap();
+        recorder.tick(&Input::default()).unwrap();
+        let replay = Replay::load(path.to_str().unwrap()).unwrap();
+        assert_eq!((replay.seed, replay.level_file), (7, Some("level2.txt".to_string())));
+        assert_eq!(replay.steps.len(), 3);
+        match (&replay.steps[0], &replay.steps[1], &replay.steps[2]) {
+            (ReplayStep::Tick(first), ReplayStep::Restart, ReplayStep::Tick(last)) => {
+                assert_eq!((encode(first).as_str(), encode(last).as_str()), ("ur", "-"));
+            },
+            steps => panic!("unexpected steps {:?}", steps),
+        }
+        drop(recorder);
+        fs::remove_file(&path).unwrap();
+    }
+}
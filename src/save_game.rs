@@ -0,0 +1,208 @@
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use ggez::{filesystem, mint, Context, GameResult};
+
+use crate::guard::GuardSnapshot;
+use crate::level::{self, LevelError};
+use crate::sim::SimSnapshot;
+
+pub const SAVE_FILENAME: &str = "savegame.txt";
+
+// Partija prekinuta iz pauze, cuva se u direktorijumu za korisnicke podatke.
+// Svaka linija fajla pocinje kljucem: run, level, seed, carried, run_ticks, ticks,
+// map_start, player, door, zatim result, guard i gold linije koliko ih ima
+#[derive(Debug, Clone)]
+pub struct SaveGame {
+    pub run_start: String, // nivo od kog je partija pocela, ostatak se racuna iz kampanje
+    pub level_index: usize,
+    pub seed: u64,
+    pub carried_gold: i32,
+    pub run_ticks: u32,
+    pub results: Vec<(String, i32, u32)>, // rezultati vec zavrsenih nivoa partije
+    pub sim: SimSnapshot,
+}
+
+fn save_path(ctx: &Context) -> PathBuf {
+    filesystem::user_data_dir(ctx).join(SAVE_FILENAME)
+}
+
+fn number<T: FromStr>(fields: &[&str], i: usize, line_no: usize, what: &str) -> Result<T, LevelError> {
+    match fields.get(i) {
+        Some(field) => level::parse_field(SAVE_FILENAME, line_no, 1, field, what),
+        None => Err(LevelError::new(SAVE_FILENAME, line_no, 1, format!("{}: missing value", what))),
+    }
+}
+
+fn step(fields: &[&str], i: usize, line_no: usize, what: &str) -> Result<i32, LevelError> {
+    // smer kroz rutu strazara, sve osim 1 i -1 bi u Guard::next_patrol_point izaslo van rute
+    match number(fields, i, line_no, what)? {
+        s if s == 1 || s == -1 => Ok(s),
+        s => Err(LevelError::new(SAVE_FILENAME, line_no, 1, format!("{}: expected 1 or -1, found {}", what, s))),
+    }
+}
+
+fn point(fields: &[&str], i: usize, line_no: usize, what: &str) -> Result<mint::Point2<f32>, LevelError> {
+    Ok(mint::Point2 { x: number(fields, i, line_no, what)?, y: number(fields, i+1, line_no, what)? })
+}
+
+impl SaveGame {
+    pub fn exists(ctx: &Context) -> bool {
+        save_path(ctx).is_file()
+    }
+
+    pub fn load(ctx: &Context) -> GameResult<Self> {
+        let text = fs::read_to_string(save_path(ctx))?;
+        Ok(SaveGame::parse(&text)?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, LevelError> {
+        let mut save = SaveGame {
+            run_start: String::new(),
+            level_index: 0,
+            seed: 0,
+            carried_gold: 0,
+            run_ticks: 0,
+            results: Vec::new(),
+            sim: SimSnapshot {
+                ticks: 0,
+                map_start: mint::Point2 { x: 0.0, y: 0.0 },
+                player_pos: mint::Point2 { x: 0.0, y: 0.0 },
+                score: 0,
+                spotted: 0,
+                guards: Vec::new(),
+                gold: Vec::new(),
+                door: mint::Point2 { x: 0.0, y: 0.0 },
+            },
+        };
+        for (i, line) in text.lines().enumerate() {
+            let n = i+1;
+            let fields: Vec<&str> = line.split(' ').collect();
+            match fields[0] {
+                "run" => save.run_start = fields.get(1).unwrap_or(&"").to_string(),
+                "level" => save.level_index = number(&fields, 1, n, "level")?,
+                "seed" => save.seed = number(&fields, 1, n, "seed")?,
+                "carried" => save.carried_gold = number(&fields, 1, n, "carried")?,
+                "run_ticks" => save.run_ticks = number(&fields, 1, n, "run_ticks")?,
+                "result" => save.results.push((fields.get(1).unwrap_or(&"").to_string(), number(&fields, 2, n, "result gold")?, number(&fields, 3, n, "result ticks")?)),
+                "ticks" => save.sim.ticks = number(&fields, 1, n, "ticks")?,
+                "map_start" => save.sim.map_start = point(&fields, 1, n, "map_start")?,
+                "player" => {
+                    save.sim.player_pos = point(&fields, 1, n, "player")?;
+                    save.sim.score = number(&fields, 3, n, "player score")?;
+                    save.sim.spotted = number(&fields, 4, n, "player spotted")?;
+                },
+                "door" => save.sim.door = point(&fields, 1, n, "door")?,
+                "guard" => save.sim.guards.push(GuardSnapshot {
+                    pos: point(&fields, 1, n, "guard")?,
+                    patrol: number(&fields, 3, n, "guard patrol")?,
+                    patrol_step: step(&fields, 4, n, "guard step")?,
+                    rotation: number(&fields, 5, n, "guard rotation")?,
+                    gave_up: number(&fields, 6, n, "guard gave up")?,
+                }),
+                "gold" => save.sim.gold.push(point(&fields, 1, n, "gold")?),
+                "" => (),
+                key => return Err(LevelError::new(SAVE_FILENAME, n, 1, format!("unknown key '{}'", key))),
+            }
+        }
+        if save.run_start.is_empty() {
+            return Err(LevelError::new(SAVE_FILENAME, 1, 1, "missing 'run' line".to_string()))
+        }
+        Ok(save)
+    }
+
+    pub fn save(&self, ctx: &Context) -> GameResult<()> {
+        let path = save_path(ctx);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, self.to_text())?;
+        Ok(())
+    }
+
+    pub fn to_text(&self) -> String {
+        let sim = &self.sim;
+        let mut text = String::new();
+        text.push_str(&format!("run {}\nlevel {}\nseed {}\ncarried {}\nrun_ticks {}\n", self.run_start, self.level_index, self.seed, self.carried_gold, self.run_ticks));
+        for (table, gold, ticks) in self.results.iter() {
+            text.push_str(&format!("result {} {} {}\n", table, gold, ticks));
+        }
+        text.push_str(&format!("ticks {}\nmap_start {} {}\n", sim.ticks, sim.map_start.x, sim.map_start.y));
+        text.push_str(&format!("player {} {} {} {}\n", sim.player_pos.x, sim.player_pos.y, sim.score, sim.spotted));
+        text.push_str(&format!("door {} {}\n", sim.door.x, sim.door.y));
+        for guard in sim.guards.iter() {
+            text.push_str(&format!("guard {} {} {} {} {} {}\n", guard.pos.x, guard.pos.y, guard.patrol, guard.patrol_step, guard.rotation, guard.gave_up));
+        }
+        for gold in sim.gold.iter() {
+            text.push_str(&format!("gold {} {}\n", gold.x, gold.y));
+        }
+        text
+    }
+
+    pub fn delete(ctx: &Context) -> GameResult<()> {
+        // sacuvana igra moze da se nastavi samo jednom
+        let path = save_path(ctx);
+        if path.is_file() {
+            fs::remove_file(&path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> SaveGame {
+        SaveGame {
+            run_start: "level1.txt".to_string(),
+            level_index: 1,
+            seed: 42,
+            carried_gold: 30,
+            run_ticks: 900,
+            results: vec![("level1.txt".to_string(), 30, 600)],
+            sim: SimSnapshot {
+                ticks: 300,
+                map_start: mint::Point2 { x: 100.0, y: 164.0 },
+                player_pos: mint::Point2 { x: 350.5, y: 200.0 },
+                score: 20,
+                spotted: 1,
+                guards: vec![GuardSnapshot {
+                    pos: mint::Point2 { x: 164.0, y: 228.0 },
+                    patrol: 2,
+                    patrol_step: -1,
+                    rotation: 1.5,
+                    gave_up: 0,
+                }],
+                gold: vec![mint::Point2 { x: 196.0, y: 260.0 }],
+                door: mint::Point2 { x: 356.0, y: 164.0 },
+            },
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let save = sample();
+        let loaded = SaveGame::parse(&save.to_text()).unwrap();
+        assert_eq!((loaded.run_start.as_str(), loaded.level_index, loaded.seed), ("level1.txt", 1, 42));
+        assert_eq!((loaded.carried_gold, loaded.run_ticks), (30, 900));
+        assert_eq!(loaded.results, save.results);
+        let (sim, expected) = (&loaded.sim, &save.sim);
+        assert_eq!((sim.ticks, sim.score, sim.spotted), (300, 20, 1));
+        assert_eq!((sim.map_start, sim.player_pos, sim.door), (expected.map_start, expected.player_pos, expected.door));
+        assert_eq!(sim.gold, expected.gold);
+        assert_eq!(sim.guards.len(), 1);
+        let guard = &sim.guards[0];
+        assert_eq!((guard.pos, guard.patrol, guard.patrol_step, guard.rotation, guard.gave_up), (expected.guards[0].pos, 2, -1, 1.5, 0));
+        assert_eq!(loaded.to_text(), save.to_text());
+    }
+
+    #[test]
+    fn rejects_bad_guard_step_and_missing_run() {
+        let text = sample().to_text().replace("guard 164 228 2 -1", "guard 164 228 2 0");
+        let e = SaveGame::parse(&text).unwrap_err();
+        assert_eq!(e.description, "guard step: expected 1 or -1, found 0");
+        let text: String = sample().to_text().lines().skip(1).map(|l| format!("{}\n", l)).collect();
+        assert_eq!(SaveGame::parse(&text).unwrap_err().description, "missing 'run' line");
+    }
+}
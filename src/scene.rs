@@ -0,0 +1,73 @@
+use ggez::*;
+use rand::{thread_rng, Rng};
+
+use crate::bindings::{Action, Bindings};
+use crate::campaign::Campaign;
+use crate::gamepad::GamepadState;
+use crate::high_scores::HighScores;
+use crate::progress::Progress;
+use crate::render::Renderer;
+use crate::ui::MenuInput;
+
+// Stanje koje dele sve scene: nivoi, sacuvani rezultati, slike i opcije
+pub struct Shared {
+    pub campaign: Campaign, // svi nivoi iz campaign.txt
+    pub progress: Progress,
+    pub high_scores: HighScores,
+    pub renderer: Renderer,
+    pub seed: Option<u64>, // --seed sa komandne linije
+    pub music_on: bool,
+    pub bindings: Bindings, // tasteri za akcije, iz bindings.txt
+    pub gamepad: GamepadState,
+}
+
+impl Shared {
+    pub fn next_seed(&self) -> u64 {
+        // ako je seed zadat, svaka partija je ista, inace biramo nov
+        match self.seed {
+            Some(seed) => seed,
+            None => thread_rng().gen_range(0, 1000000),
+        }
+    }
+}
+
+// Poruka koju scena salje sceni ispod sebe kad se skloni sa steka
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Signal {
+    RestartLevel,
+    SaveAndQuit,
+}
+
+// Sta scena trazi od steka posle svog updatea
+pub enum Transition {
+    Stay,
+    Push(Box<dyn Scene>), // nova scena preko trenutne (pauza, paneli iz menija)
+    Pop,
+    PopWith(Signal),
+    Replace(Box<dyn Scene>), // trenutna scena se menja novom (igra -> kraj partije)
+    Reset(Box<dyn Scene>), // ceo stek se brise (povratak u glavni meni, pocetak igre)
+}
+
+// Jedan ekran igre. Stek scena je u main.rs, update i unos dobija samo scena na vrhu
+pub trait Scene {
+    fn update(&mut self, ctx: &mut Context, shared: &mut Shared) -> GameResult<Transition>;
+    fn draw(&self, ctx: &mut Context, shared: &mut Shared) -> GameResult<()>;
+
+    fn key_down(&mut self, _keycode: event::KeyCode) {} // sirov taster, npr. za upis inicijala
+    fn action(&mut self, _action: Action) {} // akcija na koju je taster ili dugme vezano, npr. Pause
+    fn menu(&mut self, _input: MenuInput) {} // kretanje kroz meni sa tastature ili kontrolera
+    fn text_input(&mut self, _character: char) {}
+
+    fn signal(&mut self, _ctx: &mut Context, _shared: &mut Shared, _signal: Signal) -> GameResult<()> {
+        Ok(())
+    }
+
+    fn is_overlay(&self) -> bool {
+        // overlay scena se crta preko scene ispod sebe, npr. pauza preko igre
+        false
+    }
+
+    fn pauses_music(&self) -> bool {
+        false
+    }
+}
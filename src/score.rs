@@ -1,8 +1,70 @@
-use ggez::*;
+use crate::sim::TICKS_PER_SECOND;
+
+pub const ESCAPE_BONUS: i32 = 50; // za izlazak kroz vrata
+pub const GHOST_BONUS: i32 = 100; // nijedan strazar nije primetio igraca
+pub const CLEAN_SWEEP_BONUS: i32 = 100; // pokupljeno je sve zlato na nivou
+pub const PAR_SECONDS: i32 = 120; // za svaku sekundu ispod ovoga igrac dobija jedan zlatnik
+
+#[derive(Debug, Clone)]
+pub struct Bonus {
+    pub name: String,
+    pub gold: i32,
+}
+
+// Izvestaj o jednom nivou koji se prikazuje na kraju partije
+#[derive(Debug, Clone)]
+pub struct HeistReport {
+    pub gold_collected: i32, // samo u ovom nivou, bez zlata iz prethodnih
+    pub gold_available: i32,
+    pub ticks: u32,
+    pub times_spotted: i32,
+    pub guards_evaded: i32,
+    pub escaped: bool,
+    pub bonuses: Vec<Bonus>,
+}
+
+impl HeistReport {
+    pub fn bonus_total(&self) -> i32 {
+        self.bonuses.iter().map(|b| b.gold).sum()
+    }
+
+    pub fn seconds(&self) -> i32 {
+        (self.ticks as f32/TICKS_PER_SECOND) as i32
+    }
+}
 
 pub trait Score {
-    fn increase (&mut self, coin: i32) -> GameResult<()>;    // da povecavamo score
-    fn draw_score (&self, ctx: &mut Context)-> GameResult<()>;  // da ispise score na ekran
+    fn increase (&mut self, coin: i32);    // da povecavamo score
+    fn total (&self) -> i32;  // trenutni score, ispisuje ga renderer
+    fn spotted (&mut self);  // neki strazar je primetio igraca
+    fn times_spotted (&self) -> i32;
+
+    fn report (&self, level_gold: i32, gold_available: i32, ticks: u32, guards_evaded: i32, escaped: bool) -> HeistReport {
+        // bonusi se dobijaju samo ako je igrac pobegao i nesto ukrao. Bez zlata bi
+        // pravo trcanje do vrata donelo skoro 300 i usli bi u tabelu najboljih rezultata
+        let mut report = HeistReport {
+            gold_collected: level_gold,
+            gold_available: gold_available,
+            ticks: ticks,
+            times_spotted: self.times_spotted(),
+            guards_evaded: guards_evaded,
+            escaped: escaped,
+            bonuses: Vec::new(),
+        };
+        if escaped && level_gold > 0 {
+            report.bonuses.push(Bonus { name: "Escaped".to_string(), gold: ESCAPE_BONUS });
+            if report.times_spotted == 0 {
+                report.bonuses.push(Bonus { name: "Ghost".to_string(), gold: GHOST_BONUS });
+            }
+            if gold_available > 0 && level_gold >= gold_available {
+                report.bonuses.push(Bonus { name: "Clean sweep".to_string(), gold: CLEAN_SWEEP_BONUS });
+            }
+            if report.seconds() < PAR_SECONDS {
+                report.bonuses.push(Bonus { name: "Swift".to_string(), gold: PAR_SECONDS - report.seconds() });
+            }
+        }
+        report
+    }
 }
 
 // u struct Player sam dodala polje score
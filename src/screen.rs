@@ -0,0 +1,67 @@
+use ggez::*;
+
+pub const VIRTUAL_SIZE: (f32, f32) = (640.0, 480.0); // sve scene crtaju na platno ove velicine
+
+// Platno se na prozor crta uvecano celim brojem puta, da pikseli ostanu ostri,
+// i centrirano; ostatak prozora je crn
+pub struct Screen {
+    canvas: graphics::Canvas,
+    pub fullscreen: bool,
+}
+
+pub fn scale(ctx: &Context) -> f32 {
+    let (w, h) = graphics::drawable_size(ctx);
+    (w/VIRTUAL_SIZE.0).min(h/VIRTUAL_SIZE.1).floor().max(1.0)
+}
+
+fn origin(ctx: &Context) -> mint::Point2<f32> {
+    // gornji levi ugao platna u prozoru
+    let (w, h) = graphics::drawable_size(ctx);
+    let scale = scale(ctx);
+    mint::Point2 { x: ((w - VIRTUAL_SIZE.0*scale)/2.0).floor(), y: ((h - VIRTUAL_SIZE.1*scale)/2.0).floor() }
+}
+
+pub fn mouse_position(ctx: &Context) -> mint::Point2<f32> {
+    // pozicija misa na platnu, a ne u prozoru
+    let mouse_pos = input::mouse::position(ctx);
+    let origin = origin(ctx);
+    let scale = scale(ctx);
+    mint::Point2 { x: (mouse_pos.x - origin.x)/scale, y: (mouse_pos.y - origin.y)/scale }
+}
+
+pub fn centered_x(ctx: &mut Context, text: &graphics::Text) -> f32 {
+    // x za tekst centriran na platnu
+    ((VIRTUAL_SIZE.0 - text.dimensions(ctx).0 as f32)/2.0).floor()
+}
+
+impl Screen {
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        let mut canvas = graphics::Canvas::new(ctx, VIRTUAL_SIZE.0 as u16, VIRTUAL_SIZE.1 as u16, conf::NumSamples::One)?;
+        canvas.set_filter(graphics::FilterMode::Nearest);
+        Ok(Screen {
+            canvas: canvas,
+            fullscreen: false,
+        })
+    }
+
+    pub fn begin(&self, ctx: &mut Context) -> GameResult<()> {
+        // sve sto se crta do finish ide na platno
+        graphics::set_canvas(ctx, Some(&self.canvas));
+        graphics::set_screen_coordinates(ctx, graphics::Rect::new(0.0, 0.0, VIRTUAL_SIZE.0, VIRTUAL_SIZE.1))
+    }
+
+    pub fn finish(&self, ctx: &mut Context) -> GameResult<()> {
+        graphics::set_canvas(ctx, None);
+        let (w, h) = graphics::drawable_size(ctx);
+        graphics::set_screen_coordinates(ctx, graphics::Rect::new(0.0, 0.0, w, h))?;
+        graphics::clear(ctx, graphics::BLACK);
+        let scale = scale(ctx);
+        graphics::draw(ctx, &self.canvas, graphics::DrawParam::new().dest(origin(ctx)).scale([scale, scale]))
+    }
+
+    pub fn toggle_fullscreen(&mut self, ctx: &mut Context) -> GameResult<()> {
+        self.fullscreen = !self.fullscreen;
+        let mode = if self.fullscreen { conf::FullscreenType::Desktop } else { conf::FullscreenType::Windowed };
+        graphics::set_fullscreen(ctx, mode)
+    }
+}
@@ -0,0 +1,260 @@
+use ggez::*;
+use na::{Vector2, Isometry2};
+use ncollide2d::shape::{Cuboid, ShapeHandle};
+use ncollide2d::world::{CollisionGroups, CollisionWorld, GeometricQueryType};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use crate::camera::Camera;
+use crate::guard::{Alertness, GuardSnapshot};
+use crate::map::{Map, TILE_SIZE};
+use crate::player::Player;
+use crate::score::{HeistReport, Score};
+use crate::screen::VIRTUAL_SIZE;
+
+pub const TICKS_PER_SECOND: f32 = 30.0; // simulacija uvek napreduje u koracima od 1/30 sekunde
+pub const STICK_STEPS: f32 = 100.0; // otklon palice u Input je ceo broj, da bi snimak partije bio tacan
+
+// Stanje tastera u jednom ticku. Simulacija ne zna nista o tastaturi,
+// main.rs popunjava ovu strukturu i prosledjuje je u step
+#[derive(Debug, Clone, Default)]
+pub struct Input {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub interact: bool,
+    pub sneak: bool, // sporije i bez zvuka
+    pub sprint: bool, // brze i glasnije, dok ima izdrzljivosti
+    pub throw: bool, // baca kamencic da odvuce strazare
+    pub stick_x: i32, // analogna palica od -STICK_STEPS do STICK_STEPS, y na dole;
+    pub stick_y: i32, // kad nije na nuli, ona odredjuje pravac i brzinu umesto strelica
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Running,
+    Caught,
+    Escaped,
+}
+
+// Sta se desilo u jednom ticku, da bi renderer znao npr. kad da pusti zvuk
+#[derive(Debug, Clone, Default)]
+pub struct Events {
+    pub gold_collected: i32,
+}
+
+// Stanje nivoa u toku, za cuvanje igre. Pozicije zlata i vrata su u odnosu na map_start,
+// ostalo je u koordinatama sveta. Stare sacuvane igre su iz vremena kad se mapa pomerala,
+// pa restore sve pomera za razliku izmedju sacuvanog i pravog map_start
+#[derive(Debug, Clone)]
+pub struct SimSnapshot {
+    pub ticks: u32,
+    pub map_start: mint::Point2<f32>,
+    pub player_pos: mint::Point2<f32>,
+    pub score: i32,
+    pub spotted: i32,
+    pub guards: Vec<GuardSnapshot>,
+    pub gold: Vec<mint::Point2<f32>>, // zlato koje jos nije pokupljeno
+    pub door: mint::Point2<f32>,
+}
+
+// Cela logika igre (pozicije, kolizije, strazari, score, pobeda/poraz)
+// bez ijedne slike ili zvuka, pa moze da se pokrece i bez prozora
+pub struct Simulation {
+    pub castle_map: Map,
+    pub player: Player,
+    pub camera: Camera,
+    pub world: CollisionWorld<f32, ()>,
+    pub outcome: Outcome,
+    pub alertness: Alertness, // najveca uzbuna medju strazarima u poslednjem ticku
+    pub seed: u64,
+    pub ticks: u32, // koliko je tickova proteklo od pocetka nivoa
+    rng: StdRng, // jedini generator slucajnih brojeva u igri, da bi svaka partija mogla da se ponovi
+}
+
+impl Simulation {
+    pub fn new(level_name: &str, level_text: &str, tfrac: mint::Point2<f32>, seed: u64) -> GameResult<Self> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut world_mut = CollisionWorld::new(0.02);
+        let shape = ShapeHandle::new(Cuboid::new(Vector2::new(12.0, 8.0)));
+        let mut groups = CollisionGroups::new();
+        groups.set_membership(&[0 as usize]);
+        groups.set_blacklist(&[0 as usize]);
+        groups.set_whitelist(&[1 as usize]);
+        let query = GeometricQueryType::Contacts(0.0, 0.0);
+
+        let castle_map = Map::load(level_name, level_text, tfrac, mint::Point2 { x:100.0, y:164.0 }, mint::Point2 { x:TILE_SIZE, y:TILE_SIZE }, &mut world_mut, &mut rng)?;
+        let player = Player::new(world_mut.add(Isometry2::new(Vector2::new(64.0, 74.0), 0.0), shape.clone(), groups, query, ()).handle());
+
+        Ok(Simulation {
+            camera: Camera::new(VIRTUAL_SIZE, castle_map.bounds(), player.pos),
+            castle_map: castle_map,
+            player: player,
+            world: world_mut,
+            outcome: Outcome::Running,
+            alertness: Alertness::Calm,
+            seed: seed,
+            ticks: 0,
+            rng: rng,
+        })
+    }
+
+    pub fn step(&mut self, input: &Input) -> Events {
+        // jedan tick igre, redosled je isti kao sto je bio u GameState::update
+        let mut events = Events::default();
+        if self.outcome != Outcome::Running {
+            return events
+        }
+        self.ticks += 1;
+        self.player.update(input, &mut self.world, self.castle_map.map_handle, &mut self.castle_map.get_corners());
+        if let Some((pos, dir)) = self.player.throw(input) {
+            self.castle_map.throw_pebble(pos, dir);
+        }
+        self.camera.follow(self.player.pos);
+        self.world.update();
+        // zvuci iz ovog ticka idu na castle_map.map_noises, a strazari ih cuju u update_guards.
+        // Vrata i zlato se azuriraju posle strazara, pa se njihovi zvuci cuju u sledecem ticku
+        if let Some(footsteps) = self.player.noise() {
+            self.castle_map.map_noises.publish(footsteps);
+        }
+        self.castle_map.update_pebbles(&self.world);
+        let old_alertness = self.alertness;
+        self.alertness = self.castle_map.update_guards(&mut self.world, self.player.col_handle, &mut self.rng);
+        if old_alertness == Alertness::Calm && self.alertness != Alertness::Calm {
+            self.player.spotted(); // bar jedan strazar je postao sumnjicav
+        }
+        let escaped = self.castle_map.map_door.update(&mut self.world, self.player.col_handle, input.interact, &mut self.castle_map.map_noises);
+        events.gold_collected = self.castle_map.update_gold(&mut self.world, self.player.col_handle);
+        self.player.increase(events.gold_collected);
+
+        if self.alertness == Alertness::Caught {
+            self.outcome = Outcome::Caught;
+        } else if escaped {
+            self.outcome = Outcome::Escaped;
+        }
+        events
+    }
+
+    pub fn settle(&mut self) {
+        // igra stoji (npr. pauza), pa nema sta da se crta izmedju dva ticka
+        self.player.prev_pos = self.player.pos;
+        for guard in self.castle_map.map_guards.iter_mut() {
+            guard.prev_pos = guard.pos;
+        }
+        for pebble in self.castle_map.map_pebbles.iter_mut() {
+            pebble.prev_pos = pebble.pos;
+        }
+        self.camera.settle();
+    }
+
+    pub fn snapshot(&self) -> SimSnapshot {
+        let map_start = self.castle_map.map_start;
+        let relative = |p: mint::Point2<f32>| mint::Point2 { x: p.x - map_start.x, y: p.y - map_start.y };
+        SimSnapshot {
+            ticks: self.ticks,
+            map_start: map_start,
+            player_pos: self.player.pos,
+            score: self.player.total(),
+            spotted: self.player.times_spotted(),
+            guards: self.castle_map.map_guards.iter().map(|g| g.snapshot()).collect(),
+            gold: self.castle_map.map_gold.iter().map(|g| relative(g.pos)).collect(),
+            door: relative(self.castle_map.map_door.pos),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &SimSnapshot) -> GameResult<()> {
+        // simulacija je tek napravljena od istog nivoa i seeda, pa su strazari i zlato
+        // isti kao na pocetku sacuvanog nivoa i samo ih pomeramo na sacuvana mesta
+        if snapshot.guards.len() != self.castle_map.map_guards.len() {
+            return Err(GameError::ResourceLoadError("saved game does not match the level".to_string()))
+        }
+        for (i, (guard, saved)) in self.castle_map.map_guards.iter().zip(snapshot.guards.iter()).enumerate() {
+            if saved.patrol >= guard.route_len() {
+                return Err(GameError::ResourceLoadError(format!("saved game: guard {} is at waypoint {}, but its route has {}", i+1, saved.patrol, guard.route_len())))
+            }
+        }
+        // vrata su uvek na istom mestu u nivou, pa se njihova sacuvana pozicija ne koristi
+        let map_start = self.castle_map.map_start;
+        let to_world = |p: mint::Point2<f32>| mint::Point2 { x: p.x + map_start.x - snapshot.map_start.x, y: p.y + map_start.y - snapshot.map_start.y };
+        self.castle_map.keep_gold(&mut self.world, &snapshot.gold);
+        for (guard, saved) in self.castle_map.map_guards.iter_mut().zip(snapshot.guards.iter()) {
+            guard.restore(&GuardSnapshot { pos: to_world(saved.pos), ..saved.clone() });
+        }
+        self.player.restore(&mut self.world, to_world(snapshot.player_pos), snapshot.score, snapshot.spotted);
+        self.camera.snap(self.player.pos);
+        self.ticks = snapshot.ticks;
+        // stanje generatora ne cuvamo, pa nastavak ne mora biti isti kao da igra nije prekinuta
+        self.rng = StdRng::seed_from_u64(self.seed.wrapping_add(snapshot.ticks as u64));
+        self.world.update(); // da prvi step ne bi video sudare sa pocetnih pozicija
+        Ok(())
+    }
+
+    pub fn report(&self, carried_gold: i32) -> HeistReport {
+        // carried_gold je zlato doneto iz prethodnih nivoa, ono se ne racuna u ovaj nivo
+        self.player.report(self.player.total() - carried_gold, self.castle_map.map_gold_total, self.ticks,
+                           self.castle_map.guards_evaded(), self.outcome == Outcome::Escaped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Soba 16x8 polja okruzena zidom. Igrac uvek pocinje na (350, 200), sto je polje (7, 1),
+    // a vrata su na podu odmah pored njega, pa ih igrac dodiruje od prvog ticka
+    const ROOM: &str = "17 9 8 1
+2222222222222222
+2              2
+2              2
+2              2
+2              2
+2              2
+2              2
+2222222222222222
+";
+
+    fn tfrac() -> mint::Point2<f32> {
+        mint::Point2 { x: 0.2, y: 0.25 }
+    }
+
+    fn run(sim: &mut Simulation, input: &Input, max_ticks: u32) {
+        for _ in 0..max_ticks {
+            sim.step(input);
+            if sim.outcome != Outcome::Running {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn escapes_through_door_on_interact() {
+        let mut sim = Simulation::new("room.txt", ROOM, tfrac(), 1).unwrap();
+        run(&mut sim, &Input::default(), 30);
+        assert_eq!(sim.outcome, Outcome::Running); // dodir bez E nije izlazak
+        run(&mut sim, &Input { interact: true, ..Input::default() }, 1);
+        assert_eq!(sim.outcome, Outcome::Escaped);
+    }
+
+    #[test]
+    fn guard_catches_standing_player() {
+        // strazar stoji polje ispod igraca i gleda gore
+        let level = format!("{}guard loop 7,2:5:up\n", ROOM);
+        let mut sim = Simulation::new("room.txt", &level, tfrac(), 1).unwrap();
+        run(&mut sim, &Input::default(), 300);
+        assert_eq!(sim.outcome, Outcome::Caught);
+        assert_eq!(sim.player.times_spotted(), 1);
+    }
+
+    #[test]
+    fn same_seed_and_input_give_same_game() {
+        let level = format!("{}guard pingpong 2,2 13,2 13,6\n", ROOM);
+        let mut a = Simulation::new("room.txt", &level, tfrac(), 7).unwrap();
+        let mut b = Simulation::new("room.txt", &level, tfrac(), 7).unwrap();
+        let input = Input { down: true, right: true, ..Input::default() };
+        run(&mut a, &input, 60);
+        run(&mut b, &input, 60);
+        assert_eq!(a.player.pos, b.player.pos);
+        assert_eq!(a.castle_map.map_guards[0].pos, b.castle_map.map_guards[0].pos);
+    }
+}
@@ -0,0 +1,42 @@
+use ggez::*;
+
+use crate::screen;
+
+// Pomocne funkcije za tekstove na kojima se klikce, zajednicke za panele
+
+pub fn hovered(ctx: &mut Context, text: &graphics::Text, pos: mint::Point2<f32>) -> bool {
+    let mouse_pos = screen::mouse_position(ctx);
+    let (w, h) = text.dimensions(ctx);
+    mouse_pos.x >= pos.x && mouse_pos.x < pos.x + w as f32 && mouse_pos.y >= pos.y && mouse_pos.y < pos.y + h as f32
+}
+
+pub fn highlight(text: &mut graphics::Text, on: bool) {
+    // zuta boja kad je mis preko teksta, kao u MainMenu i GameOver
+    for fragment in text.fragments_mut() {
+        fragment.color = Some(if on { (208, 198, 29, 255).into() } else { [1.0, 1.0, 1.0, 1.0].into() });
+    }
+}
+
+// Kretanje kroz meni bez misa (strelice, Enter i Esc, ili D-pad i dugmad na kontroleru)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+}
+
+pub fn menu_input(keycode: event::KeyCode) -> Option<MenuInput> {
+    // tasteri za meni su uvek isti, ne zavise od bindings.txt
+    match keycode {
+        event::KeyCode::Up => Some(MenuInput::Up),
+        event::KeyCode::Down => Some(MenuInput::Down),
+        event::KeyCode::Left => Some(MenuInput::Left),
+        event::KeyCode::Right => Some(MenuInput::Right),
+        event::KeyCode::Return => Some(MenuInput::Confirm),
+        event::KeyCode::Escape => Some(MenuInput::Back),
+        _ => None,
+    }
+}